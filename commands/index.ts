@@ -1,31 +1,62 @@
-import { join } from "path";
-import tsPlugin from "./plugins/typescript";
+import { Orchestrator } from "./utils/binding";
+import type { OrchestratorParams } from "./utils/binding";
+
+type Plugin = {
+  inputResolver?: (wsPath: string) => Promise<Array<string>>;
+};
+
 /**
- * __dirname means load native addon from current dir
- * 'sculpture-cli' is the name of native addon
- * the second arguments was decided by `napi.name` field in `package.json`
- * the third arguments was decided by `name` field in `package.json`
- * `loadBinding` helper will load `sculpture-cli.[PLATFORM].node` from `__dirname` first
- * If failed to load addon, it will fallback to load from `sculpture-cli-[PLATFORM]`
+ * Input resolvers are native and picked per workspace, by the "resolvers"
+ * list in the "sculpture" config or by the config files present (tsconfig,
+ * babel, vite). These plugins are one more resolver, called "js", which
+ * runs for every workspace that doesn't configure its resolvers.
  */
-let { loadBinding } = require("@node-rs/helper");
-let { Orchestrator } = loadBinding(
-  join(__dirname, "..", ".."),
-  "sculpture-cli",
-  "sculpture-cli"
-);
-let plugins = [tsPlugin];
+let plugins: Array<Plugin> = [];
 
 /**
  * Use JSDoc comments to define help and parameters for a CLI.
  * {cliName} will be replaced with an actual name of a CLI tool.
  *
- * @usage {cliName} inputs --param1 10 --param2 20
+ * @usage {cliName} --profile trace.json
+ * @usage {cliName} --filter "app..." --filter "!legacy"
+ * @usage {cliName} build --output grouped
+ *
+ * @param {string[]} $inputs Script to run in the affected workspaces, e.g. "build"
+ * @param {string}   [profile] Write a Chrome Trace Event profile of the run to a file
+ * @param {string[]} [filter] Scope the run with pnpm-style filters: name, "@scope/*", "app...", "...lib", "./packages/**", "[origin/main]", "!name"
+ * @param {boolean}  [fix] Rewrite dependency ranges that break the version policy
+ * @param {string}   [output] How task output is printed: "stream" with [workspace:task] prefixes, "grouped" per task or "errors-only"
+ * @param {number}   [concurrency] Tasks running at once, the number of CPUs by default
+ * @param {boolean}  [continueOnError] Only skip tasks depending on a failed one instead of stopping all tasks
+ * @param {number}   [timeout] Seconds after which tasks without a timeout of their own are terminated
  */
-export default async function main() {
+export default async function main(
+  [task]: string[] = [],
+  profile?: string,
+  filter?: string | string[],
+  fix?: boolean,
+  output?: OrchestratorParams["output"],
+  concurrency?: number,
+  continueOnError?: boolean,
+  timeout?: number
+) {
   let start = Date.now();
 
-  await run();
+  try {
+    await run({
+      profile,
+      filter,
+      fix,
+      task,
+      output,
+      concurrency,
+      continueOnError,
+      timeout,
+    });
+  } catch (e) {
+    console.error(`Run failed: ${e.message}`);
+    process.exit(1);
+  }
 
   let timing = (Date.now() - start) / 1000;
   let rounded = Math.round(timing * 100) / 100;
@@ -33,28 +64,44 @@ export default async function main() {
   process.exit(0);
 }
 
-function run() {
-  return new Promise((resolve) => {
-    let orchestrator = new Orchestrator({
-      cwd: process.cwd(),
-      onFinish() {
-        resolve(undefined);
-      },
-      async onResolveInputs(_err: unknown, id: string, wsPath: string) {
-        let promises = plugins.reduce((acc, plugin) => {
-          if (!plugin.inputResolver) return acc;
-          acc.push(plugin.inputResolver(wsPath));
-          return acc;
-        }, [] as Array<Promise<Array<String>>>);
-
-        let files = Array.from(
-          new Set((await Promise.all(promises)).flatMap((file) => file))
-        );
-
-        orchestrator.onCompleteJsTask(id, JSON.stringify(files));
-      },
-    });
+type RunOptions = Omit<OrchestratorParams, "cwd" | "onResolveInputs">;
+
+async function run(options: RunOptions) {
+  let resolvers = plugins.flatMap((plugin) =>
+    plugin.inputResolver ? [plugin.inputResolver] : []
+  );
+  let orchestrator = new Orchestrator({
+    cwd: process.cwd(),
+    ...options,
+    onResolveInputs: resolvers.length
+      ? async (_err: unknown, id: string, wsPath: string) => {
+          let results = await Promise.all(
+            resolvers.map((resolver) => resolver(wsPath))
+          );
+          let files = Array.from(new Set(results.flat()));
 
-    orchestrator.run(10);
+          orchestrator.onCompleteJsTask(id, JSON.stringify(files));
+        }
+      : undefined,
   });
+
+  // Tasks run in process groups of their own, out of reach of the terminal's
+  // Ctrl-C, so the signal is forwarded to them before exiting by it.
+  let signal: NodeJS.Signals | undefined;
+  let onSignal = (received: NodeJS.Signals) => {
+    signal = received;
+    orchestrator.cancel();
+  };
+  process.on("SIGINT", onSignal);
+  process.on("SIGTERM", onSignal);
+
+  try {
+    return await orchestrator.run();
+  } finally {
+    process.off("SIGINT", onSignal);
+    process.off("SIGTERM", onSignal);
+    if (signal) {
+      process.kill(process.pid, signal);
+    }
+  }
 }
@@ -0,0 +1,39 @@
+import { Orchestrator } from "./utils/binding";
+
+/**
+ * Writes a workspace and every workspace it depends on to a separate
+ * directory, with a trimmed root package.json and a pruned lockfile
+ *
+ * @usage {cliName} prune web-app
+ * @usage {cliName} prune web-app --out-dir docker-out --docker
+ * @param {Array<string>} $inputs Name of the workspace to prune the project to
+ * @param {string}  [outDir] Output directory, "out" by default
+ * @param {boolean} [docker] Split the output into "json" (manifests and lockfile only) and "full" (sources) for Docker layer caching
+ */
+export default async function prune(
+  [workspace]: Array<string> = [],
+  outDir?: string,
+  docker?: boolean
+) {
+  if (!workspace) {
+    console.error("Workspace name is required: prune <workspace>");
+    process.exit(1);
+  }
+
+  let orchestrator = new Orchestrator({ cwd: process.cwd() });
+
+  try {
+    let workspaces = await orchestrator.prune({
+      workspace,
+      outDir,
+      docker,
+    });
+    console.log(`Pruned to ${workspaces.length} workspaces:`);
+    for (let name of workspaces) {
+      console.log(`  ${name}`);
+    }
+  } catch (e) {
+    console.error(e.message);
+    process.exit(1);
+  }
+}
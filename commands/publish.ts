@@ -0,0 +1,42 @@
+import { Orchestrator } from "./utils/binding";
+
+/**
+ * Publishes non-private workspaces whose version isn't in the registry yet,
+ * dependencies first. Dependents of a workspace that fails to publish are
+ * skipped
+ *
+ * @usage {cliName} publish
+ * @usage {cliName} publish --registry http://localhost:4873
+ * @param {string} [registry] Registry to check and publish to, npm's configuration by default
+ */
+export default async function publish(
+  $inputs: Array<string>,
+  registry?: string
+) {
+  let orchestrator = new Orchestrator({ cwd: process.cwd() });
+
+  try {
+    let results = await orchestrator.publish({ registry });
+    let failed = 0;
+    for (let result of results) {
+      let pkg = `${result.name}@${result.version}`;
+      switch (result.status) {
+        case "published":
+          console.log(`Published ${pkg}`);
+          break;
+        case "already_published":
+          console.log(`${pkg} is already published`);
+          break;
+        default:
+          failed++;
+          console.error(`Didn't publish ${pkg}: ${result.error}`);
+      }
+    }
+    if (failed > 0) {
+      process.exit(1);
+    }
+  } catch (e) {
+    console.error(e.message);
+    process.exit(1);
+  }
+}
@@ -0,0 +1,90 @@
+import type { RunSummary } from "./utils/binding";
+import { listRuns, readRun, runsDir } from "./utils/runs";
+import { formatWhy } from "./utils/why";
+
+function printList(runs: Array<RunSummary>) {
+  if (!runs.length) {
+    console.log("No runs recorded yet.");
+    return;
+  }
+
+  for (let run of runs) {
+    let date = new Date(run.started_at).toISOString();
+    let status = run.error ? "failed" : "ok";
+    console.log(
+      `${run.id}  ${date}  ${run.duration}ms  ${status}  updated: ${run.updated.length}  affected: ${run.affected.length}`
+    );
+  }
+}
+
+function printRun(run: RunSummary) {
+  console.log(`Run ${run.id}`);
+  console.log(`  Started:  ${new Date(run.started_at).toISOString()}`);
+  console.log(`  Finished: ${new Date(run.finished_at).toISOString()}`);
+  console.log(`  Duration: ${run.duration}ms`);
+  if (run.error) {
+    console.log(`  Error:    ${run.error}`);
+  }
+
+  console.log();
+  console.log("Timings:");
+  for (let timing of run.timings) {
+    console.log(`  ${timing.name}: ${timing.duration}ms`);
+  }
+
+  console.log();
+  console.log("Workspaces:");
+  for (let ws of run.workspaces) {
+    console.log(`  ${ws.name} [${ws.status}]`);
+    if (ws.why && ws.status === "affected") {
+      console.log(`    ${formatWhy(ws.why)}`);
+    }
+    for (let file of ws.changed_files) {
+      console.log(`    ~ ${file}`);
+    }
+  }
+
+  if (run.tasks.length) {
+    console.log();
+    console.log("Tasks:");
+    for (let task of run.tasks) {
+      let cache = task.cache_hit ? " (cache hit)" : "";
+      let exitCode = task.exit_code === null ? "" : ` exit ${task.exit_code}`;
+      let retried = task.attempts > 1 ? ` after ${task.attempts} attempts` : "";
+      let blockedBy = task.blocked_by ? ` (${task.blocked_by} didn't succeed)` : "";
+      console.log(
+        `  ${task.workspace}:${task.task} [${task.status}] ${task.duration}ms${exitCode}${retried}${blockedBy}${cache}`
+      );
+      let failed = task.status === "failed" || task.status === "timed_out";
+      if (failed && task.log_file) {
+        console.log(`    log: ${task.log_file}`);
+      }
+    }
+  }
+}
+
+/**
+ * Lists persisted run summaries, or shows a single run in detail
+ *
+ * @usage {cliName} runs
+ * @usage {cliName} runs show 1612345678901
+ * @param {Array<string>} $inputs Optional "show <id>" to inspect a single run
+ */
+export default async function runs([action, id]: Array<string> = []) {
+  if (action === "show") {
+    if (!id) {
+      console.error("Run id is required: runs show <id>");
+      process.exit(1);
+    }
+
+    try {
+      printRun(await readRun(id));
+    } catch {
+      console.error(`Run "${id}" not found in ${runsDir()}`);
+      process.exit(1);
+    }
+    return;
+  }
+
+  printList(await listRuns());
+}
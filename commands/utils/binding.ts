@@ -0,0 +1,33 @@
+import { join } from "path";
+import type { Orchestrator as OrchestratorClass } from "../../index";
+
+/**
+ * __dirname means load native addon from current dir
+ * 'sculpture-cli' is the name of native addon
+ * the second arguments was decided by `napi.name` field in `package.json`
+ * the third arguments was decided by `name` field in `package.json`
+ * `loadBinding` helper will load `sculpture-cli.[PLATFORM].node` from `__dirname` first
+ * If failed to load addon, it will fallback to load from `sculpture-cli-[PLATFORM]`
+ */
+let { loadBinding } = require("@node-rs/helper");
+let binding: { Orchestrator: typeof OrchestratorClass } = loadBinding(
+  join(__dirname, "..", ".."),
+  "sculpture-cli",
+  "sculpture-cli"
+);
+
+export let { Orchestrator } = binding;
+export type {
+  Bump,
+  Change,
+  DependencyUpdate,
+  Explanation,
+  FileChange,
+  OrchestratorParams,
+  PruneOptions,
+  PublishOptions,
+  PublishResult,
+  Release,
+  RunSummary,
+  TaskSummary,
+} from "../../index";
@@ -0,0 +1,26 @@
+import { promises } from "fs";
+import { join } from "path";
+import type { RunSummary } from "./binding";
+
+export let runsDir = () => join(process.cwd(), ".cache", "runs");
+
+export async function readRun(id: string): Promise<RunSummary> {
+  let contents = await promises.readFile(join(runsDir(), `${id}.json`), "utf8");
+  return JSON.parse(contents);
+}
+
+/** Persisted run summaries, latest first */
+export async function listRuns(): Promise<Array<RunSummary>> {
+  let files: Array<string>;
+  try {
+    files = await promises.readdir(runsDir());
+  } catch {
+    return [];
+  }
+
+  let ids = files
+    .filter((file) => file.endsWith(".json"))
+    .map((file) => file.replace(/\.json$/, ""));
+  let runs = await Promise.all(ids.map(readRun));
+  return runs.sort((a, b) => b.started_at - a.started_at);
+}
@@ -0,0 +1,35 @@
+import type { Explanation, FileChange } from "./binding";
+
+const MAX_FILES = 3;
+
+let shortHash = (hash: string | null) => (hash ? hash.slice(0, 7) : "");
+
+function formatChange(change: FileChange) {
+  if (!change.old_hash) {
+    return `${change.path} was added`;
+  }
+  if (!change.new_hash) {
+    return `${change.path} was removed`;
+  }
+  return `${change.path} changed (hash ${shortHash(change.old_hash)}→${shortHash(change.new_hash)})`;
+}
+
+/**
+ * "web" is affected because it depends on "ui" → "tokens", and
+ * packages/tokens/src/colors.ts changed (hash abc1234→def5678)
+ */
+export function formatWhy(why: Explanation) {
+  let [name, ...dependencies] = why.chain;
+  let changes = why.changes.slice(0, MAX_FILES).map(formatChange);
+  if (why.changes.length > MAX_FILES) {
+    changes.push(`and ${why.changes.length - MAX_FILES} more files changed`);
+  }
+  let reason = changes.join(", ");
+
+  if (!dependencies.length) {
+    return `"${name}" is updated because ${reason}`;
+  }
+
+  let chain = dependencies.map((dep) => `"${dep}"`).join(" → ");
+  return `"${name}" is affected because it depends on ${chain}, and ${reason}`;
+}
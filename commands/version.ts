@@ -0,0 +1,32 @@
+import { Orchestrator } from "./utils/binding";
+
+/**
+ * Applies the changesets in .changeset: bumps the versions they declare,
+ * patch-bumps dependents whose ranges would otherwise break, rewrites
+ * those ranges and prepends CHANGELOG.md entries
+ *
+ * @usage {cliName} version
+ */
+export default async function version() {
+  let orchestrator = new Orchestrator({ cwd: process.cwd() });
+
+  try {
+    let releases = await orchestrator.version();
+    if (releases.length === 0) {
+      console.log("No changesets to apply");
+      return;
+    }
+
+    for (let release of releases) {
+      console.log(
+        `${release.name}: ${release.old_version} → ${release.new_version} (${release.bump})`
+      );
+      for (let update of release.dependency_updates) {
+        console.log(`  ${update.name}: ${update.old_spec} → ${update.new_spec}`);
+      }
+    }
+  } catch (e) {
+    console.error(e.message);
+    process.exit(1);
+  }
+}
@@ -0,0 +1,45 @@
+import { formatWhy } from "./utils/why";
+import { listRuns, readRun } from "./utils/runs";
+
+/**
+ * Explains why a workspace was updated or affected in a run: the chain of
+ * dependencies back to the change and the files that changed
+ *
+ * @usage {cliName} why web-app
+ * @usage {cliName} why web-app --run 1612345678901
+ * @param {Array<string>} $inputs Name of the workspace to explain
+ * @param {string} [run] Id of the run, the latest one by default
+ */
+export default async function why(
+  [workspace]: Array<string> = [],
+  run?: string
+) {
+  if (!workspace) {
+    console.error("Workspace name is required: why <workspace>");
+    process.exit(1);
+  }
+
+  let summary;
+  try {
+    summary = run ? await readRun(run) : (await listRuns())[0];
+  } catch {
+    console.error(`Run "${run}" not found`);
+    process.exit(1);
+  }
+  if (!summary) {
+    console.error("No runs recorded yet.");
+    process.exit(1);
+  }
+
+  let ws = summary.workspaces.find((ws) => ws.name === workspace);
+  if (!ws) {
+    console.error(`Unknown workspace "${workspace}" in run ${summary.id}`);
+    process.exit(1);
+  }
+
+  console.log(
+    ws.why
+      ? formatWhy(ws.why)
+      : `"${workspace}" is unchanged in run ${summary.id}`
+  );
+}
@@ -0,0 +1,318 @@
+//! Native CLI, for environments without Node. JS plugins still need `node`
+//! to be installed, they are run through `--plugin`.
+
+use sculpture_core::executor::{FailurePolicy, OutputMode};
+use sculpture_core::publish::PublishStatus;
+use sculpture_core::resolvers::NodeResolver;
+use sculpture_core::runner::{SharedCache, SharedGraph, SharedProject};
+use sculpture_core::{
+    publish_project, query_affected, query_graph, run, version_project, DepGraph, FileCache,
+    GraphQuery, Project, PublishOptions, ResolverRegistry, RunOptions, RunSummary,
+};
+use serde_json::Value;
+use std::path::{Path, MAIN_SEPARATOR};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+const USAGE: &str = "Usage: scu <command> [options]
+
+Commands:
+  run [task]             Invalidate inputs, validate the graph and find affected workspaces,
+                         then run the task script in them
+  affected [names...]    Workspaces affected by changes since the last run, or by the given ones
+  graph [name] [to]      Workspaces in topological order, dependencies of a workspace,
+                         or the dependency path between two
+  validate               Validate the dependency graph
+  version                Apply the changesets in .changeset: bump versions and ranges,
+                         prepend changelog entries
+  publish                Publish new versions of non-private workspaces, dependencies first
+  cache <path|clean>     Print or remove the cache directory
+
+Options:
+  --cwd <dir>            Project root, the current directory by default
+  --filter <expr>        pnpm-style filter, can be repeated
+  --profile <file>       Write a Chrome Trace Event profile of the run
+  --fix                  Rewrite dependency ranges that break the version policy
+  --output <mode>        run: stream (prefixed lines), grouped or errors-only task output
+  --concurrency <n>      run: tasks running at once, the number of CPUs by default
+  --continue-on-error    run: only skip dependents of failed tasks instead of stopping all
+  --timeout <seconds>    run: terminate tasks without a timeout of their own after this long
+  --plugin <file>        JS module exporting an inputResolver, run with node, can be repeated
+  --dependents           graph: dependents instead of dependencies
+  --transitive           graph: include indirect dependencies or dependents
+  --registry <url>       publish: registry to check and publish to, npm's configuration by default
+  --json                 Print results as JSON";
+
+#[derive(Debug, Default)]
+struct Args {
+    command: String,
+    inputs: Vec<String>,
+    cwd: Option<String>,
+    plugins: Vec<String>,
+    options: RunOptions,
+    publish: PublishOptions,
+    dependents: bool,
+    transitive: bool,
+    json: bool,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| {
+                iter.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+            match arg.as_str() {
+                "--cwd" => parsed.cwd = Some(value(arg)?),
+                "--filter" => parsed.options.filter.push(value(arg)?),
+                "--profile" => parsed.options.profile = Some(value(arg)?),
+                "--plugin" => parsed.plugins.push(value(arg)?),
+                "--fix" => parsed.options.fix = true,
+                "--output" => parsed.options.executor.output = OutputMode::parse(&value(arg)?)?,
+                "--continue-on-error" => parsed.options.executor.policy = FailurePolicy::Continue,
+                "--timeout" => {
+                    let timeout = value(arg)?;
+                    parsed.options.executor.timeout = Some(
+                        timeout
+                            .parse()
+                            .map_err(|_| format!("Invalid timeout \"{}\"", timeout))?,
+                    );
+                }
+                "--concurrency" => {
+                    let concurrency = value(arg)?;
+                    parsed.options.executor.concurrency = Some(
+                        concurrency
+                            .parse()
+                            .map_err(|_| format!("Invalid concurrency \"{}\"", concurrency))?,
+                    );
+                }
+                "--registry" => parsed.publish.registry = Some(value(arg)?),
+                "--dependents" => parsed.dependents = true,
+                "--transitive" => parsed.transitive = true,
+                "--json" => parsed.json = true,
+                flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+                _ if parsed.command.is_empty() => parsed.command = arg.to_owned(),
+                _ => parsed.inputs.push(arg.to_owned()),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = Args::parse(&args).and_then(execute) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn execute(mut args: Args) -> Result<(), String> {
+    let cwd = match &args.cwd {
+        Some(cwd) => cwd.to_owned(),
+        None => std::env::current_dir()
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let cache_path = format!("{}{}{}", cwd, MAIN_SEPARATOR, ".cache");
+    if args.command == "run" {
+        args.options.task = args.inputs.first().cloned();
+        args.options.executor.catch_interrupts = true;
+    }
+
+    match args.command.as_str() {
+        "run" => {
+            let start = Instant::now();
+            run_project(&cwd, cache_path, &args)?;
+            println!(
+                "Done in {:.2}s.",
+                start.elapsed().as_millis() as f64 / 1000.0
+            );
+            Ok(())
+        }
+        "affected" => {
+            let names = if args.inputs.is_empty() {
+                let (project, _, _) = open_project_quietly(&cwd, cache_path);
+                query_affected(&project, &resolvers(&cwd, &args)?, &args.options)?
+            } else {
+                let query = GraphQuery::Affected(args.inputs.clone());
+                as_list(query_project(&cwd, cache_path, &query)?)
+            };
+            print_list(&names, args.json);
+            Ok(())
+        }
+        "graph" => {
+            let query = match args.inputs.as_slice() {
+                [] => GraphQuery::TopologicalOrder,
+                [name] if args.dependents => GraphQuery::Dependents {
+                    name: name.to_owned(),
+                    transitive: args.transitive,
+                },
+                [name] => GraphQuery::Dependencies {
+                    name: name.to_owned(),
+                    transitive: args.transitive,
+                },
+                [from, to] => GraphQuery::Path {
+                    from: from.to_owned(),
+                    to: to.to_owned(),
+                },
+                _ => return Err(USAGE.to_owned()),
+            };
+            let result = query_project(&cwd, cache_path, &query)?;
+            if result.is_null() {
+                return Err(format!("No dependency path {}", args.inputs.join(" → ")));
+            }
+            print_list(&as_list(result), args.json);
+            Ok(())
+        }
+        "validate" => {
+            let project = Project::new(cwd);
+            let graph = DepGraph::new(project.workspaces.values().cloned().collect());
+            if graph.validate(project.config()) {
+                println!("Dependency graph is valid");
+                Ok(())
+            } else {
+                Err("Dependency graph is invalid".to_owned())
+            }
+        }
+        "version" => {
+            let (project, _, _) = open_project_quietly(&cwd, cache_path);
+            let releases = version_project(&project)?;
+            if releases.is_empty() {
+                println!("No changesets to apply");
+            }
+            for release in releases {
+                println!(
+                    "{}: {} → {} ({})",
+                    release.name,
+                    release.old_version,
+                    release.new_version,
+                    release.bump.as_str()
+                );
+                for update in &release.dependency_updates {
+                    println!(
+                        "  {}: {} → {}",
+                        update.name, update.old_spec, update.new_spec
+                    );
+                }
+            }
+            Ok(())
+        }
+        "publish" => {
+            let (project, _, _) = open_project_quietly(&cwd, cache_path);
+            let results = publish_project(&project, &args.publish)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&results).unwrap());
+            }
+            let mut failed = 0;
+            for result in &results {
+                let package = format!("{}@{}", result.name, result.version);
+                let message = match result.status {
+                    PublishStatus::Published => format!("Published {}", package),
+                    PublishStatus::AlreadyPublished => format!("{} is already published", package),
+                    PublishStatus::Failed | PublishStatus::Skipped => {
+                        failed += 1;
+                        format!(
+                            "Didn't publish {}: {}",
+                            package,
+                            result.error.as_deref().unwrap_or_default()
+                        )
+                    }
+                };
+                if !args.json {
+                    println!("{}", message);
+                }
+            }
+            if failed > 0 {
+                Err(format!("{} workspaces weren't published", failed))
+            } else {
+                Ok(())
+            }
+        }
+        "cache" => match args.inputs.first().map(String::as_str) {
+            Some("path") => {
+                println!("{}", cache_path);
+                Ok(())
+            }
+            Some("clean") => match std::fs::remove_dir_all(&cache_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => {
+                    println!("Removed {}", cache_path);
+                    Ok(())
+                }
+            },
+            _ => Err(USAGE.to_owned()),
+        },
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+fn open_project(cwd: &str, cache_path: String) -> (SharedProject, SharedCache, SharedGraph) {
+    let cache = FileCache::new(cache_path);
+    let project = Project::create_or_cached(&cache, cwd);
+    shared(project, cache)
+}
+
+/// Without the logs of `create_or_cached`, queries print only results.
+fn open_project_quietly(
+    cwd: &str,
+    cache_path: String,
+) -> (SharedProject, SharedCache, SharedGraph) {
+    let cache = FileCache::new(cache_path);
+    let project = Project::cached(&cache).unwrap_or_else(|| Project::new(cwd.to_owned()));
+    shared(project, cache)
+}
+
+fn shared(project: Project, cache: FileCache) -> (SharedProject, SharedCache, SharedGraph) {
+    (
+        Arc::new(RwLock::new(project)),
+        Arc::new(RwLock::new(cache)),
+        Arc::new(RwLock::new(None)),
+    )
+}
+
+fn run_project(cwd: &str, cache_path: String, args: &Args) -> Result<RunSummary, String> {
+    let (project, cache, graph) = open_project(cwd, cache_path);
+    let resolvers = resolvers(cwd, args)?;
+    run(&project, &resolvers, &cache, &graph, &args.options)
+}
+
+/// The builtin resolvers, and the `--plugin` ones run through `node`.
+fn resolvers(cwd: &str, args: &Args) -> Result<ResolverRegistry, String> {
+    let mut resolvers = ResolverRegistry::builtin();
+    if !args.plugins.is_empty() {
+        let plugins = args
+            .plugins
+            .iter()
+            .map(|plugin| Path::new(cwd).join(plugin).to_string_lossy().into_owned())
+            .collect();
+        resolvers.register(Arc::new(NodeResolver::new(plugins)?));
+    }
+    Ok(resolvers)
+}
+
+fn query_project(cwd: &str, cache_path: String, query: &GraphQuery) -> Result<Value, String> {
+    let (project, _, graph) = open_project_quietly(cwd, cache_path);
+    query_graph(&project, &graph, query)
+}
+
+fn as_list(value: Value) -> Vec<String> {
+    serde_json::from_value(value).unwrap_or_default()
+}
+
+fn print_list(names: &[String], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(names).unwrap());
+    } else {
+        for name in names {
+            println!("{}", name);
+        }
+    }
+}
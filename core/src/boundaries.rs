@@ -0,0 +1,123 @@
+//! Tag based rules restricting dependencies between workspaces.
+
+use super::config::Config;
+use super::workspace::Workspace;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Restricts which workspaces may depend on which. Selectors are either
+/// `tag:<name>` or a workspace name/glob:
+///
+/// ```json
+/// { "from": "tag:ui", "allow": ["tag:ui", "tag:util"] }
+/// { "from": "tag:shared", "deny": ["tag:feature"] }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BoundaryRule {
+    pub from: String,
+
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    pub from: String,
+    pub to: String,
+    pub rule: String,
+}
+
+impl BoundaryRule {
+    fn check(
+        &self,
+        from: &str,
+        from_tags: &BTreeSet<String>,
+        to: &str,
+        to_tags: &BTreeSet<String>,
+    ) -> Option<String> {
+        if !matches_selector(&self.from, from, from_tags) {
+            return None;
+        }
+
+        if let Some(allow) = &self.allow {
+            if !allow.iter().any(|s| matches_selector(s, to, to_tags)) {
+                return Some(format!("\"{}\" may only depend on {:?}", self.from, allow));
+            }
+        }
+
+        self.deny
+            .iter()
+            .find(|s| matches_selector(s, to, to_tags))
+            .map(|s| format!("\"{}\" must not depend on \"{}\"", self.from, s))
+    }
+}
+
+/// Tags of every workspace: the ones from its own `package.json` plus the
+/// ones assigned by `workspaceTags` in the root config.
+pub fn collect_tags(
+    workspaces: &[Workspace],
+    config: &Config,
+) -> HashMap<String, BTreeSet<String>> {
+    let patterns: Vec<(Pattern, &Vec<String>)> = config
+        .workspace_tags
+        .iter()
+        .filter_map(|(pattern, tags)| match Pattern::new(pattern) {
+            Ok(p) => Some((p, tags)),
+            Err(e) => {
+                println!("Invalid workspaceTags pattern \"{}\": {}", pattern, e);
+                None
+            }
+        })
+        .collect();
+
+    workspaces
+        .iter()
+        .map(|ws| {
+            let mut tags: BTreeSet<String> = ws.package_json.config.tags.iter().cloned().collect();
+            for (pattern, extra) in &patterns {
+                if pattern.matches(&ws.name) {
+                    tags.extend(extra.iter().cloned());
+                }
+            }
+            (ws.name.clone(), tags)
+        })
+        .collect()
+}
+
+/// Checks every workspace-to-workspace edge against the boundary rules.
+pub fn check_boundaries<'a>(
+    edges: impl Iterator<Item = (&'a String, &'a String)>,
+    tags: &HashMap<String, BTreeSet<String>>,
+    rules: &[BoundaryRule],
+) -> Vec<Violation> {
+    let empty = BTreeSet::new();
+    let mut violations = vec![];
+
+    for (from, to) in edges {
+        let from_tags = tags.get(from).unwrap_or(&empty);
+        let to_tags = tags.get(to).unwrap_or(&empty);
+        for rule in rules {
+            if let Some(reason) = rule.check(from, from_tags, to, to_tags) {
+                violations.push(Violation {
+                    from: from.clone(),
+                    to: to.clone(),
+                    rule: reason,
+                });
+            }
+        }
+    }
+
+    violations.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    violations
+}
+
+fn matches_selector(selector: &str, name: &str, tags: &BTreeSet<String>) -> bool {
+    match selector.strip_prefix("tag:") {
+        Some(tag) => tags.contains(tag),
+        None => Pattern::new(selector).map_or(selector == name, |p| p.matches(name)),
+    }
+}
@@ -0,0 +1,104 @@
+//! `CHANGELOG.md` entries of released workspaces.
+
+use super::changeset::Bump;
+use super::release::Release;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A version section with the changeset summaries grouped by bump, the same
+/// layout as changelogs written by hand, e.g.:
+///
+/// ```md
+/// ## 2.2.0
+///
+/// ### Minor Changes
+///
+/// - brave-lions-sing: Add dark mode colors
+///
+/// ### Patch Changes
+///
+/// - Updated dependencies: tokens@1.0.1
+/// ```
+pub fn entry(release: &Release) -> String {
+    let mut entry = format!("## {}\n", release.new_version);
+
+    for (bump, title) in [
+        (Bump::Major, "Major Changes"),
+        (Bump::Minor, "Minor Changes"),
+        (Bump::Patch, "Patch Changes"),
+    ] {
+        let mut items: Vec<String> = release
+            .changes
+            .iter()
+            .filter(|change| change.bump == bump)
+            .map(|change| item(&format!("{}: {}", change.id, change.summary)))
+            .collect();
+        if bump == Bump::Patch && !release.dependency_updates.is_empty() {
+            let dependencies: Vec<String> = release
+                .dependency_updates
+                .iter()
+                .map(|update| format!("{}@{}", update.package, update.version))
+                .collect();
+            items.push(item(&format!(
+                "Updated dependencies: {}",
+                dependencies.join(", ")
+            )));
+        }
+        if items.is_empty() {
+            continue;
+        }
+
+        entry.push_str(&format!("\n### {}\n\n", title));
+        for item in items {
+            entry.push_str(&item);
+        }
+    }
+
+    entry
+}
+
+/// Contents of `CHANGELOG.md` in the workspace with the entry of the
+/// release on top, below its title, along with its path. Titled with the
+/// workspace name when there's no file yet.
+pub fn prepended(ws_path: &str, release: &Release) -> Result<(PathBuf, String), String> {
+    let path = Path::new(ws_path).join("CHANGELOG.md");
+    let existing = match fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => format!("# {}\n", release.name),
+        Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+    };
+
+    let (title, rest) = if existing.starts_with("# ") {
+        existing.split_at(existing.find('\n').map_or(existing.len(), |i| i + 1))
+    } else {
+        ("", existing.as_str())
+    };
+    let rest = rest.trim_start_matches('\n');
+
+    let mut contents = String::from(title);
+    if !title.is_empty() {
+        contents.push('\n');
+    }
+    contents.push_str(&entry(release));
+    if !rest.is_empty() {
+        contents.push('\n');
+        contents.push_str(rest);
+    }
+
+    Ok((path, contents))
+}
+
+/// A list item, continuation lines indented under it.
+fn item(text: &str) -> String {
+    let mut item = String::from("-");
+    for (i, line) in text.lines().enumerate() {
+        match (i, line.is_empty()) {
+            (0, _) => item.push(' '),
+            (_, true) => {}
+            (_, false) => item.push_str("  "),
+        }
+        item.push_str(line);
+        item.push('\n');
+    }
+    item
+}
@@ -0,0 +1,129 @@
+//! Changesets: markdown files in `.changeset` declaring version bumps.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Directory in the project root changesets are read from.
+pub const CHANGESET_DIR: &str = ".changeset";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// A pending change, e.g. `.changeset/brave-lions-sing.md`:
+///
+/// ```md
+/// ---
+/// "@acme/ui": minor
+/// tokens: patch
+/// ---
+///
+/// Add dark mode colors
+/// ```
+#[derive(Debug, Clone)]
+pub struct Changeset {
+    /// File name without the extension.
+    pub id: String,
+    pub path: String,
+    pub releases: Vec<(String, Bump)>,
+    pub summary: String,
+}
+
+impl Bump {
+    pub fn parse(bump: &str) -> Result<Self, String> {
+        match bump {
+            "patch" => Ok(Bump::Patch),
+            "minor" => Ok(Bump::Minor),
+            "major" => Ok(Bump::Major),
+            _ => Err(format!(
+                "Unknown bump \"{}\", expected major, minor or patch",
+                bump
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+impl Changeset {
+    pub fn parse(id: &str, path: &str, contents: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid changeset {}: {}", path, reason);
+
+        let mut lines = contents.lines();
+        if lines.next().map(str::trim) != Some("---") {
+            return Err(invalid("expected a \"---\" front matter"));
+        }
+
+        let mut releases = vec![];
+        let mut closed = false;
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "---" {
+                closed = true;
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, bump) = line.rsplit_once(':').ok_or_else(|| {
+                invalid(&format!("expected \"<package>: <bump>\", got \"{}\"", line))
+            })?;
+            let name = name.trim().trim_matches(|c| c == '"' || c == '\'');
+            let bump = Bump::parse(bump.trim()).map_err(|e| invalid(&e))?;
+            releases.push((name.to_owned(), bump));
+        }
+        if !closed {
+            return Err(invalid("the front matter isn't closed with \"---\""));
+        }
+
+        Ok(Self {
+            id: id.to_owned(),
+            path: path.to_owned(),
+            releases,
+            summary: lines.collect::<Vec<_>>().join("\n").trim().to_owned(),
+        })
+    }
+
+    /// Changesets of the project, sorted by id. `README.md` is left for
+    /// describing the directory.
+    pub fn read_all(root: &str) -> Result<Vec<Self>, String> {
+        let dir = Path::new(root).join(CHANGESET_DIR);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Couldn't read {}: {}", dir.display(), e)),
+        };
+
+        let mut changesets = vec![];
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(id) if path.extension().is_some_and(|ext| ext == "md") => id.to_owned(),
+                _ => continue,
+            };
+            if id.eq_ignore_ascii_case("readme") {
+                continue;
+            }
+
+            let path = path.to_string_lossy().into_owned();
+            let contents =
+                fs::read_to_string(&path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+            changesets.push(Self::parse(&id, &path, &contents)?);
+        }
+        changesets.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(changesets)
+    }
+}
@@ -0,0 +1,52 @@
+//! The `sculpture` config in `package.json` files.
+
+use super::boundaries::BoundaryRule;
+use super::version_policy::VersionPolicyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tool configuration, read from the `sculpture` field of a `package.json`.
+/// Most options only make sense in the root one, `tags` only in a
+/// workspace one.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    #[serde(default)]
+    pub version_policy: VersionPolicyConfig,
+
+    /// Tags of the workspace this `package.json` belongs to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Workspace name or glob -> tags, for tagging workspaces from the root.
+    #[serde(default)]
+    pub workspace_tags: HashMap<String, Vec<String>>,
+
+    #[serde(default)]
+    pub boundaries: Vec<BoundaryRule>,
+
+    /// Input resolvers by name. Those of a workspace replace the root ones,
+    /// without any, resolvers are picked by the config files present.
+    #[serde(default)]
+    pub resolvers: Vec<String>,
+
+    /// Take hashes of files missing from the cache, e.g. on a fresh checkout
+    /// in CI, from `git ls-files -s` instead of reading them.
+    #[serde(default)]
+    pub git_hashes: bool,
+
+    /// Threads hashing input files, the number of CPUs by default. Also the
+    /// most input files open at once.
+    #[serde(default)]
+    pub hash_threads: Option<usize>,
+
+    /// Task name -> times a failed task is run again, for known-flaky
+    /// suites. Those of a workspace replace the root ones.
+    #[serde(default)]
+    pub retries: HashMap<String, u32>,
+
+    /// Task name -> seconds after which the task is terminated, along with
+    /// the processes it started. Those of a workspace replace the root ones.
+    #[serde(default)]
+    pub timeouts: HashMap<String, u64>,
+}
@@ -0,0 +1,661 @@
+//! Parsing of npm dependency specs and `workspace:` ranges.
+
+use crate::paths::normalize;
+use semver::{Identifier, Version};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// A parsed npm dependency spec, i.e. the value side of a `dependencies`
+/// entry in `package.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepSpec {
+    /// `^1.2.3`, `~1.2`, `1.x`, `*`, `>=1 <2`, `1.0.0 - 2.0.0`, `1 || 2`
+    Range(NpmRange),
+    /// `latest`, `next`, `beta`
+    Tag(String),
+    /// `workspace:*`, `workspace:^`, `workspace:~`, `workspace:^1.2.3`
+    Workspace(WorkspaceRange),
+    /// `npm:other@^1.0.0`
+    Alias { name: String, spec: Box<DepSpec> },
+    /// `file:../x`
+    File(String),
+    /// `link:../x`
+    Link(String),
+    /// `git+https://...`, `github:user/repo`, `user/repo#ref`
+    Git(String),
+    /// `https://example.com/pkg.tgz`
+    Url(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkspaceRange {
+    Any,
+    Caret,
+    Tilde,
+    Range(NpmRange),
+}
+
+impl DepSpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+
+        if let Some(rest) = spec.strip_prefix("workspace:") {
+            let range = match rest {
+                "*" => WorkspaceRange::Any,
+                "^" => WorkspaceRange::Caret,
+                "~" => WorkspaceRange::Tilde,
+                _ => WorkspaceRange::Range(NpmRange::parse(rest)?),
+            };
+            return Ok(DepSpec::Workspace(range));
+        }
+
+        if let Some(rest) = spec.strip_prefix("npm:") {
+            let (name, inner) = split_alias(rest).unwrap_or((rest, "*"));
+            if !is_package_name(name) {
+                return Err(format!(
+                    "Invalid npm: alias \"{}\", expected \"npm:<name>@<range>\"",
+                    spec
+                ));
+            }
+            return Ok(DepSpec::Alias {
+                name: name.to_owned(),
+                spec: Box::new(DepSpec::parse(inner)?),
+            });
+        }
+
+        if let Some(path) = spec.strip_prefix("file:") {
+            return Ok(DepSpec::File(path.to_owned()));
+        }
+
+        if let Some(path) = spec.strip_prefix("link:") {
+            return Ok(DepSpec::Link(path.to_owned()));
+        }
+
+        // Before git, "../x" would pass for a GitHub shorthand.
+        if spec.starts_with("./")
+            || spec.starts_with("../")
+            || spec.starts_with('/')
+            || spec.starts_with("~/")
+        {
+            return Ok(DepSpec::File(spec.to_owned()));
+        }
+
+        if is_git(spec) {
+            return Ok(DepSpec::Git(spec.to_owned()));
+        }
+
+        if spec.starts_with("http://") || spec.starts_with("https://") {
+            return Ok(DepSpec::Url(spec.to_owned()));
+        }
+
+        match NpmRange::parse(spec) {
+            Ok(range) => Ok(DepSpec::Range(range)),
+            Err(e) => {
+                if is_tag(spec) {
+                    Ok(DepSpec::Tag(spec.to_owned()))
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    /// Name of the package this spec actually installs. Differs from the
+    /// dependency key only for `npm:` aliases.
+    pub fn package_name<'a>(&'a self, dep_name: &'a str) -> &'a str {
+        match self {
+            DepSpec::Alias { name, .. } => name,
+            _ => dep_name,
+        }
+    }
+
+    /// Directory a `file:` or `link:` spec points at, resolved against the
+    /// directory of the workspace that declares it.
+    pub fn local_path(&self, base: &str) -> Option<PathBuf> {
+        match self {
+            DepSpec::File(path) | DepSpec::Link(path) => {
+                Some(normalize(&Path::new(base).join(path)))
+            }
+            DepSpec::Alias { spec, .. } => spec.local_path(base),
+            _ => None,
+        }
+    }
+
+    /// Whether the spec accepts the given version. `None` means the spec
+    /// can't be checked against a version at all (git, urls, tags, paths).
+    pub fn matches(&self, version: &Version) -> Option<bool> {
+        match self {
+            DepSpec::Range(range) => Some(range.matches(version)),
+            DepSpec::Workspace(WorkspaceRange::Range(range)) => Some(range.matches(version)),
+            DepSpec::Workspace(_) => Some(true),
+            DepSpec::Alias { spec, .. } => spec.matches(version),
+            _ => None,
+        }
+    }
+}
+
+/// A spec accepting `version` in the style of `old_spec`: `^` and `~` ranges
+/// keep their operator, exact versions stay exact, anything else becomes a
+/// `^` range. `npm:` aliases and `workspace:` ranges keep their prefix.
+pub fn spec_for_version(old_spec: &str, version: &Version) -> String {
+    if let Some(range) = old_spec.strip_prefix("workspace:") {
+        return format!("workspace:{}", spec_for_version(range, version));
+    }
+    if let Some((name, range)) = old_spec.strip_prefix("npm:").and_then(split_alias) {
+        return format!("npm:{}@{}", name, spec_for_version(range, version));
+    }
+
+    let prefix = match old_spec.chars().next() {
+        Some('^') => "^",
+        Some('~') => "~",
+        _ if Version::parse(old_spec).is_ok() => "",
+        _ => "^",
+    };
+    format!("{}{}", prefix, version)
+}
+
+/// Splits the part of an `npm:` alias after the prefix into the package name
+/// and its range. The name may be scoped, so the separator is the last "@"
+/// that isn't the first character. `None` when there's no range.
+pub fn split_alias(alias: &str) -> Option<(&str, &str)> {
+    match alias.rfind('@') {
+        Some(idx) if idx > 0 => Some((&alias[..idx], &alias[idx + 1..])),
+        _ => None,
+    }
+}
+
+/// An npm semver range: a union (`||`) of comparator sets that all have to
+/// match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpmRange {
+    sets: Vec<Vec<Comparator>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// A possibly incomplete version like `1`, `1.2`, `1.x` or `1.2.3-beta.1`.
+#[derive(Debug, Clone)]
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Identifier>,
+}
+
+impl NpmRange {
+    pub fn parse(range: &str) -> Result<Self, String> {
+        let mut sets = vec![];
+        for set in range.split("||") {
+            sets.push(parse_comparator_set(set.trim()).map_err(|e| {
+                format!("\"{}\" is not a valid version range: {}", range.trim(), e)
+            })?);
+        }
+        Ok(Self { sets })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| set_matches(set, version))
+    }
+
+    /// Lowest version that satisfies the range, if any.
+    pub fn min_version(&self) -> Option<Version> {
+        let mut result: Option<Version> = None;
+        for set in &self.sets {
+            let mut lower = Version::new(0, 0, 0);
+            for cmp in set {
+                let candidate = match cmp.op {
+                    Op::Eq | Op::Gte => cmp.version.clone(),
+                    Op::Gt => {
+                        let mut v = cmp.version.clone();
+                        if v.is_prerelease() {
+                            v.pre.push(Identifier::Numeric(0));
+                        } else {
+                            v.increment_patch();
+                        }
+                        v
+                    }
+                    Op::Lt | Op::Lte => continue,
+                };
+                if candidate > lower {
+                    lower = candidate;
+                }
+            }
+            if set_matches(set, &lower) && result.as_ref().is_none_or(|r| &lower < r) {
+                result = Some(lower);
+            }
+        }
+        result
+    }
+}
+
+fn parse_comparator_set(set: &str) -> Result<Vec<Comparator>, String> {
+    if set.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let tokens: Vec<&str> = set.split_whitespace().collect();
+    if tokens.len() == 3 && tokens[1] == "-" {
+        return parse_hyphen(tokens[0], tokens[2]);
+    }
+
+    let mut comparators = vec![];
+    let mut pending_op: Option<&str> = None;
+    for token in tokens {
+        if is_operator(token) {
+            if pending_op.is_some() {
+                return Err(format!("unexpected \"{}\"", token));
+            }
+            pending_op = Some(token);
+            continue;
+        }
+
+        let token = match pending_op.take() {
+            Some(op) => format!("{}{}", op, token),
+            None => token.to_owned(),
+        };
+        comparators.extend(parse_comparator(&token)?);
+    }
+
+    if let Some(op) = pending_op {
+        return Err(format!("\"{}\" is missing a version", op));
+    }
+
+    Ok(comparators)
+}
+
+fn parse_hyphen(from: &str, to: &str) -> Result<Vec<Comparator>, String> {
+    let from = Partial::parse(from)?;
+    let to = Partial::parse(to)?;
+    let mut comparators = vec![];
+    if from.major.is_some() {
+        comparators.push(Comparator::new(Op::Gte, from.floor()));
+    }
+    match to.ceil() {
+        Some(ceil) if to.patch.is_none() => comparators.push(Comparator::new(Op::Lt, ceil)),
+        _ if to.major.is_some() => comparators.push(Comparator::new(Op::Lte, to.floor())),
+        _ => {}
+    }
+    Ok(comparators)
+}
+
+fn parse_comparator(token: &str) -> Result<Vec<Comparator>, String> {
+    let (op, rest) = split_operator(token);
+    let partial = Partial::parse(rest)?;
+
+    if partial.major.is_none() {
+        return Ok(match op {
+            "<" | ">" => vec![Comparator::new(Op::Lt, Version::new(0, 0, 0))],
+            _ => vec![],
+        });
+    }
+
+    let floor = partial.floor();
+    let comparators = match op {
+        "^" => {
+            let ceil = if partial.major != Some(0) || partial.minor.is_none() {
+                Version::new(floor.major + 1, 0, 0)
+            } else if partial.minor != Some(0) || partial.patch.is_none() {
+                Version::new(0, floor.minor + 1, 0)
+            } else {
+                Version::new(0, 0, floor.patch + 1)
+            };
+            vec![
+                Comparator::new(Op::Gte, floor),
+                Comparator::new(Op::Lt, ceil),
+            ]
+        }
+        "~" | "~>" => {
+            let ceil = if partial.minor.is_none() {
+                Version::new(floor.major + 1, 0, 0)
+            } else {
+                Version::new(floor.major, floor.minor + 1, 0)
+            };
+            vec![
+                Comparator::new(Op::Gte, floor),
+                Comparator::new(Op::Lt, ceil),
+            ]
+        }
+        "" | "=" => match partial.ceil() {
+            Some(ceil) => vec![
+                Comparator::new(Op::Gte, floor),
+                Comparator::new(Op::Lt, ceil),
+            ],
+            None => vec![Comparator::new(Op::Eq, floor)],
+        },
+        ">" => match partial.ceil() {
+            Some(ceil) => vec![Comparator::new(Op::Gte, ceil)],
+            None => vec![Comparator::new(Op::Gt, floor)],
+        },
+        ">=" => vec![Comparator::new(Op::Gte, floor)],
+        "<" => vec![Comparator::new(Op::Lt, floor)],
+        "<=" => match partial.ceil() {
+            Some(ceil) => vec![Comparator::new(Op::Lt, ceil)],
+            None => vec![Comparator::new(Op::Lte, floor)],
+        },
+        _ => return Err(format!("unknown operator \"{}\"", op)),
+    };
+
+    Ok(comparators)
+}
+
+fn split_operator(token: &str) -> (&str, &str) {
+    for op in &["~>", ">=", "<=", "^", "~", ">", "<", "="] {
+        if let Some(rest) = token.strip_prefix(op) {
+            return (op, rest.trim());
+        }
+    }
+    ("", token)
+}
+
+fn is_operator(token: &str) -> bool {
+    matches!(token, "^" | "~" | "~>" | ">" | ">=" | "<" | "<=" | "=")
+}
+
+fn set_matches(set: &[Comparator], version: &Version) -> bool {
+    if !set.iter().all(|cmp| cmp.matches(version)) {
+        return false;
+    }
+
+    // Same as npm: a prerelease only satisfies a range if one of the
+    // comparators opts into prereleases of the same major.minor.patch.
+    if version.is_prerelease() {
+        return set.iter().any(|cmp| {
+            cmp.version.is_prerelease()
+                && cmp.version.major == version.major
+                && cmp.version.minor == version.minor
+                && cmp.version.patch == version.patch
+        });
+    }
+
+    true
+}
+
+impl Comparator {
+    fn new(op: Op, version: Version) -> Self {
+        Self { op, version }
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        let ord = version.cmp(&self.version);
+        match self.op {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Gte => ord != Ordering::Less,
+            Op::Lt => ord == Ordering::Less,
+            Op::Lte => ord != Ordering::Greater,
+        }
+    }
+}
+
+impl Partial {
+    fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim().trim_start_matches('v').trim_start_matches('=');
+        let (core, pre) = match input.find(['-', '+']) {
+            Some(idx) => (&input[..idx], &input[idx..]),
+            None => (input, ""),
+        };
+
+        let mut parts = core.split('.');
+        let major = parse_part(parts.next())?;
+        let minor = parse_part(parts.next())?;
+        let patch = parse_part(parts.next())?;
+        if parts.next().is_some() {
+            return Err(format!("\"{}\" has too many version parts", input));
+        }
+        if (major.is_none() && minor.is_some()) || (minor.is_none() && patch.is_some()) {
+            return Err(format!("\"{}\" is not a valid version", input));
+        }
+
+        let pre = match (major, minor, patch) {
+            (Some(major), Some(minor), Some(patch)) if pre.starts_with('-') => {
+                let full = format!("{}.{}.{}{}", major, minor, patch, pre);
+                Version::parse(&full)
+                    .map_err(|e| format!("\"{}\": {}", input, e))?
+                    .pre
+            }
+            _ => vec![],
+        };
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn floor(&self) -> Version {
+        let mut version = Version::new(
+            self.major.unwrap_or(0),
+            self.minor.unwrap_or(0),
+            self.patch.unwrap_or(0),
+        );
+        version.pre = self.pre.clone();
+        version
+    }
+
+    /// Exclusive upper bound for an incomplete version, e.g. `1.2` -> `1.3.0`.
+    fn ceil(&self) -> Option<Version> {
+        match (self.major, self.minor, self.patch) {
+            (Some(major), None, _) => Some(Version::new(major + 1, 0, 0)),
+            (Some(major), Some(minor), None) => Some(Version::new(major, minor + 1, 0)),
+            _ => None,
+        }
+    }
+}
+
+fn parse_part(part: Option<&str>) -> Result<Option<u64>, String> {
+    match part {
+        None | Some("x") | Some("X") | Some("*") => Ok(None),
+        Some(p) => p
+            .parse::<u64>()
+            .map(Some)
+            .map_err(|_| format!("\"{}\" is not a number", p)),
+    }
+}
+
+fn is_git(spec: &str) -> bool {
+    if spec.starts_with("git+")
+        || spec.starts_with("git://")
+        || spec.starts_with("git@")
+        || spec.starts_with("github:")
+        || spec.starts_with("gitlab:")
+        || spec.starts_with("bitbucket:")
+        || spec.starts_with("gist:")
+        || spec.ends_with(".git")
+    {
+        return true;
+    }
+
+    // GitHub shorthand: "user/repo" or "user/repo#ref"
+    let repo = spec.split('#').next().unwrap_or("");
+    let mut parts = repo.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(user), Some(name), None) => {
+            !user.is_empty() && !name.is_empty() && !user.starts_with('@') && !user.contains(':')
+        }
+        _ => false,
+    }
+}
+
+/// Loose check of a package name, `name` or `@scope/name`, enough to tell a
+/// name from a malformed alias.
+fn is_package_name(name: &str) -> bool {
+    let unscoped = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, name)) if !scope.is_empty() => name,
+            _ => return false,
+        },
+        None => name,
+    };
+    !unscoped.is_empty()
+        && !unscoped.contains(['/', '@'])
+        && !unscoped.contains(char::is_whitespace)
+}
+
+fn is_tag(spec: &str) -> bool {
+    !spec.is_empty()
+        && spec
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !spec.chars().next().unwrap().is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    fn range(spec: &str) -> NpmRange {
+        NpmRange::parse(spec).unwrap()
+    }
+
+    #[test]
+    fn parses_ranges_and_tags() {
+        assert_eq!(
+            DepSpec::parse("^1.2.3"),
+            Ok(DepSpec::Range(range("^1.2.3")))
+        );
+        assert_eq!(
+            DepSpec::parse(" latest "),
+            Ok(DepSpec::Tag("latest".to_owned()))
+        );
+        assert!(DepSpec::parse("1.2.3.4").is_err());
+        assert!(DepSpec::parse(">=").is_err());
+    }
+
+    #[test]
+    fn parses_empty_spec_as_any_version() {
+        let spec = DepSpec::parse("").unwrap();
+        assert_eq!(spec.matches(&version("3.1.4")), Some(true));
+    }
+
+    #[test]
+    fn parses_workspace_ranges() {
+        assert_eq!(
+            DepSpec::parse("workspace:*"),
+            Ok(DepSpec::Workspace(WorkspaceRange::Any))
+        );
+        assert_eq!(
+            DepSpec::parse("workspace:^"),
+            Ok(DepSpec::Workspace(WorkspaceRange::Caret))
+        );
+        assert_eq!(
+            DepSpec::parse("workspace:~"),
+            Ok(DepSpec::Workspace(WorkspaceRange::Tilde))
+        );
+
+        let spec = DepSpec::parse("workspace:^1.2.0").unwrap();
+        assert_eq!(
+            spec,
+            DepSpec::Workspace(WorkspaceRange::Range(range("^1.2.0")))
+        );
+        assert_eq!(spec.matches(&version("1.9.0")), Some(true));
+        assert_eq!(spec.matches(&version("2.0.0")), Some(false));
+        assert!(DepSpec::parse("workspace:nope!").is_err());
+    }
+
+    #[test]
+    fn parses_aliases() {
+        let spec = DepSpec::parse("npm:lodash@^4.17.0").unwrap();
+        assert_eq!(spec.package_name("_"), "lodash");
+        assert_eq!(spec.matches(&version("4.17.21")), Some(true));
+
+        let spec = DepSpec::parse("npm:@scope/pkg@~1.2").unwrap();
+        assert_eq!(spec.package_name("_"), "@scope/pkg");
+        assert_eq!(spec.matches(&version("1.3.0")), Some(false));
+
+        let spec = DepSpec::parse("npm:@scope/pkg").unwrap();
+        assert_eq!(spec.package_name("_"), "@scope/pkg");
+        assert_eq!(spec.matches(&version("0.0.1")), Some(true));
+
+        let spec = DepSpec::parse("npm:é@1").unwrap();
+        assert_eq!(spec.package_name("_"), "é");
+    }
+
+    #[test]
+    fn rejects_malformed_aliases() {
+        for spec in ["npm:", "npm:@", "npm:@1.0.0", "npm:@scope@1", "npm:a/b@1"] {
+            let error = DepSpec::parse(spec).unwrap_err();
+            assert!(
+                error.starts_with("Invalid npm: alias"),
+                "{}: {}",
+                spec,
+                error
+            );
+        }
+        assert!(DepSpec::parse("npm:pkg@not a range").is_err());
+    }
+
+    #[test]
+    fn parses_paths_git_and_urls() {
+        assert_eq!(
+            DepSpec::parse("file:../x"),
+            Ok(DepSpec::File("../x".to_owned()))
+        );
+        assert_eq!(
+            DepSpec::parse("link:../x"),
+            Ok(DepSpec::Link("../x".to_owned()))
+        );
+        assert_eq!(DepSpec::parse("../x"), Ok(DepSpec::File("../x".to_owned())));
+        assert_eq!(
+            DepSpec::parse("user/repo#main"),
+            Ok(DepSpec::Git("user/repo#main".to_owned()))
+        );
+        assert_eq!(
+            DepSpec::parse("https://example.com/pkg.tgz"),
+            Ok(DepSpec::Url("https://example.com/pkg.tgz".to_owned()))
+        );
+        assert_eq!(
+            DepSpec::parse("file:./a/../b")
+                .unwrap()
+                .local_path("/repo/ws"),
+            Some(PathBuf::from("/repo/ws/b"))
+        );
+    }
+
+    #[test]
+    fn rejects_non_ascii_specs() {
+        assert!(DepSpec::parse("é").is_err());
+        assert!(DepSpec::parse("^1.é").is_err());
+    }
+
+    #[test]
+    fn matches_ranges_like_npm() {
+        assert!(range("^0.2.3").matches(&version("0.2.9")));
+        assert!(!range("^0.2.3").matches(&version("0.3.0")));
+        assert!(range("1.x || >=3 <4").matches(&version("3.5.0")));
+        assert!(range("1.0.0 - 1.2").matches(&version("1.2.9")));
+        assert!(!range("^1.0.0").matches(&version("1.1.0-beta.1")));
+        assert!(range("^1.1.0-beta.0").matches(&version("1.1.0-beta.1")));
+        assert_eq!(range(">1.2.3 <2").min_version(), Some(version("1.2.4")));
+    }
+
+    #[test]
+    fn keeps_the_style_of_the_old_spec() {
+        let v = version("2.1.0");
+        assert_eq!(spec_for_version("~1.0.0", &v), "~2.1.0");
+        assert_eq!(spec_for_version("1.0.0", &v), "2.1.0");
+        assert_eq!(spec_for_version(">=1", &v), "^2.1.0");
+        assert_eq!(spec_for_version("workspace:^1.0.0", &v), "workspace:^2.1.0");
+        assert_eq!(spec_for_version("npm:@s/p@~1.0.0", &v), "npm:@s/p@~2.1.0");
+        assert_eq!(spec_for_version("npm:", &v), "^2.1.0");
+        assert_eq!(spec_for_version("npm:é@1.0.0", &v), "npm:é@2.1.0");
+    }
+}
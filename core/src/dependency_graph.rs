@@ -0,0 +1,411 @@
+//! Graph of dependencies between the workspaces of a project.
+
+use super::boundaries::{check_boundaries, collect_tags};
+use super::config::Config;
+use super::dep_spec::DepSpec;
+use super::version_policy::VersionPolicy;
+use super::workspace::Workspace;
+use semver::Version;
+use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+type DepMap = HashMap<String, (String, Vec<(String, String)>)>;
+
+/// Affected workspace → the dependency that affected it, `None` for the
+/// updated ones.
+pub type Provenance = HashMap<String, Option<String>>;
+
+/// Direct and inverted edges between workspaces. Dependencies on packages
+/// outside the project are kept, but never followed.
+#[derive(Debug)]
+pub struct DepGraph {
+    direct: DepMap,
+    inversed: DepMap,
+    invalid_specs: Vec<String>,
+    unlinked: Vec<String>,
+    workspaces: Vec<Workspace>,
+}
+
+impl DepGraph {
+    /// Builds the graph, collecting problems for `validate` to report.
+    pub fn new(workspaces: Vec<Workspace>) -> Self {
+        let mut invalid_specs = vec![];
+        let mut unlinked = vec![];
+        let direct = Self::build_direct_dep_graph(&workspaces, &mut invalid_specs, &mut unlinked);
+        let inversed = Self::build_inversed_dep_graph(&direct);
+
+        Self {
+            direct,
+            inversed,
+            invalid_specs,
+            unlinked,
+            workspaces,
+        }
+    }
+
+    /// The updated workspaces and every workspace depending on them,
+    /// dependencies first.
+    pub fn get_affected(&self, updated_workspaces: Vec<String>) -> Result<Vec<String>, String> {
+        let provenance = self.trace_affected(updated_workspaces);
+        self.top_sort(provenance.into_keys().collect())
+    }
+
+    /// Same walk as `get_affected`, recording for every affected workspace
+    /// the dependency it was first reached through. The walk is breadth
+    /// first, so following it back gives the shortest chain to a change.
+    pub fn trace_affected(&self, updated_workspaces: Vec<String>) -> Provenance {
+        let mut provenance: Provenance = HashMap::new();
+        let mut queue = VecDeque::new();
+        for ws in updated_workspaces {
+            if !provenance.contains_key(&ws) {
+                provenance.insert(ws.clone(), None);
+                queue.push_back(ws);
+            }
+        }
+
+        while let Some(cur) = queue.pop_front() {
+            if let Some((_, dependents)) = self.inversed.get(&cur) {
+                for (_, ws) in dependents {
+                    if !provenance.contains_key(ws) {
+                        provenance.insert(ws.to_owned(), Some(cur.clone()));
+                        queue.push_back(ws.to_owned());
+                    }
+                }
+            }
+        }
+
+        provenance
+    }
+
+    /// Workspaces `name` depends on, directly unless `transitive`.
+    pub fn dependencies(&self, name: &str, transitive: bool) -> HashSet<String> {
+        self.collect_edges(&self.direct, name, transitive)
+    }
+
+    /// Workspaces depending on `name`, directly unless `transitive`.
+    pub fn dependents(&self, name: &str, transitive: bool) -> HashSet<String> {
+        self.collect_edges(&self.inversed, name, transitive)
+    }
+
+    pub fn has_workspace(&self, name: &str) -> bool {
+        self.direct.contains_key(name)
+    }
+
+    pub fn workspace_names(&self) -> HashSet<String> {
+        self.direct.keys().cloned().collect()
+    }
+
+    /// Shortest chain of workspaces from `from` to `to` following
+    /// dependency edges, both ends included.
+    pub fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut parents: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(cur) = queue.pop_front() {
+            if cur == to {
+                let mut path = vec![cur.to_owned()];
+                let mut node = cur;
+                while let Some(parent) = parents.get(node) {
+                    path.push((*parent).to_owned());
+                    node = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            if let Some((_, edges)) = self.direct.get(cur) {
+                for (_, ws) in edges {
+                    if ws != from
+                        && self.direct.contains_key(ws)
+                        && !parents.contains_key(ws.as_str())
+                    {
+                        parents.insert(ws, cur);
+                        queue.push_back(ws);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn collect_edges(&self, graph: &DepMap, name: &str, transitive: bool) -> HashSet<String> {
+        let mut result = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(name.to_owned());
+
+        while let Some(cur) = queue.pop_front() {
+            if let Some((_, edges)) = graph.get(&cur) {
+                for (_, ws) in edges {
+                    if self.direct.contains_key(ws) && result.insert(ws.to_owned()) && transitive {
+                        queue.push_back(ws.to_owned());
+                    }
+                }
+            }
+        }
+
+        result.remove(name);
+        result
+    }
+
+    /// Orders the given workspaces so that each comes after its dependencies.
+    /// Fails on a cycle.
+    pub fn top_sort(&self, workspaces: HashSet<String>) -> Result<Vec<String>, String> {
+        fn dfs(
+            cur: &str,
+            graph: &DepMap,
+            workspaces: &HashSet<String>,
+            visited: &mut HashSet<String>,
+            path: &mut HashSet<String>,
+            sorted: &mut Vec<String>,
+        ) -> Result<(), String> {
+            if path.contains(cur) {
+                let mut path_vec = path.iter().collect::<Vec<&String>>();
+                let cur_copy = cur.to_owned();
+                path_vec.push(&cur_copy);
+                path_vec.reverse();
+                return Err(format!("Cycle detected: {:?}", path_vec));
+            }
+
+            path.insert(cur.to_owned());
+
+            if !graph.contains_key(cur) {
+                visited.insert(cur.to_owned());
+                sorted.push(cur.to_owned());
+                path.remove(cur);
+                return Ok(());
+            }
+
+            for (_, dep) in &graph.get(cur).unwrap().1 {
+                if workspaces.contains(dep) && !visited.contains(dep) {
+                    match dfs(dep, graph, workspaces, visited, path, sorted) {
+                        Ok(_) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+
+            visited.insert(cur.to_owned());
+            path.remove(cur);
+            sorted.push(cur.to_owned());
+
+            Ok(())
+        }
+
+        let mut sorted_workspaces: Vec<String> = vec![];
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut path: HashSet<String> = HashSet::new();
+
+        for ws in &workspaces {
+            if visited.contains(ws) {
+                continue;
+            }
+
+            match dfs(
+                ws,
+                &self.direct,
+                &workspaces,
+                &mut visited,
+                &mut path,
+                &mut sorted_workspaces,
+            ) {
+                Ok(_) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(sorted_workspaces)
+    }
+
+    /// Checks dependency specs, versions, boundaries and cycles, printing
+    /// every problem found.
+    pub fn validate(&self, config: &Config) -> bool {
+        let specs = self.validate_specs();
+        let versions = self.validate_versions(config);
+        let boundaries = self.validate_boundaries(config);
+        specs && versions && boundaries && self.validate_cycles()
+    }
+
+    fn validate_boundaries(&self, config: &Config) -> bool {
+        if config.boundaries.is_empty() {
+            return true;
+        }
+
+        let tags = collect_tags(&self.workspaces, config);
+        let edges = self.direct.iter().flat_map(|(ws, (_, deps))| {
+            deps.iter()
+                .filter(move |(_, dep)| self.direct.contains_key(dep))
+                .map(move |(_, dep)| (ws, dep))
+        });
+        let violations = check_boundaries(edges, &tags, &config.boundaries);
+
+        for violation in &violations {
+            println!(
+                "Boundary violation: \"{}\" depends on \"{}\", but {}",
+                violation.from, violation.to, violation.rule
+            );
+        }
+
+        violations.is_empty()
+    }
+
+    fn validate_specs(&self) -> bool {
+        for message in &self.unlinked {
+            println!("Warning: {}", message);
+        }
+        for message in &self.invalid_specs {
+            println!("{}", message);
+        }
+        self.invalid_specs.is_empty()
+    }
+
+    fn validate_versions(&self, config: &Config) -> bool {
+        let mut is_valid = true;
+        for ws in &self.workspaces {
+            if let Err(e) = Version::parse(&ws.package_json.version) {
+                println!(
+                    "Package \"{}\" has an invalid version \"{}\": {}",
+                    ws.name, ws.package_json.version, e
+                );
+                is_valid = false;
+            }
+        }
+
+        let mismatches = VersionPolicy::new(&config.version_policy).check(self.workspaces.iter());
+        for mismatch in &mismatches {
+            mismatch.print();
+        }
+
+        is_valid && mismatches.is_empty()
+    }
+
+    fn validate_cycles(&self) -> bool {
+        match self.top_sort(self.workspace_names()) {
+            Ok(_) => true,
+            Err(e) => {
+                println!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// Mirrors the package manager: a dependency on a sibling workspace is
+    /// only linked when its range is satisfied by the local version, or when
+    /// it uses the `workspace:`, `file:` or `link:` protocols. Anything else
+    /// would be fetched from the registry and isn't an edge in the graph.
+    fn build_direct_dep_graph(
+        workspaces: &[Workspace],
+        invalid_specs: &mut Vec<String>,
+        unlinked: &mut Vec<String>,
+    ) -> DepMap {
+        let mut map = HashMap::new();
+        let paths: HashMap<PathBuf, String> = workspaces
+            .iter()
+            .map(|ws| (PathBuf::from(&ws.path), ws.name.clone()))
+            .collect();
+        let versions: HashMap<&str, &str> = workspaces
+            .iter()
+            .map(|ws| (ws.name.as_str(), ws.package_json.version.as_str()))
+            .collect();
+
+        for ws in workspaces {
+            let mut deps = vec![];
+
+            for (dep, version) in &ws.package_json.dependencies {
+                let name = match DepSpec::parse(version) {
+                    Ok(spec) => {
+                        let name = Self::resolve_dep_name(dep, &spec, ws, &paths);
+                        // The package manager refuses to install these.
+                        if matches!(spec, DepSpec::Workspace(_))
+                            && !versions.contains_key(name.as_str())
+                        {
+                            invalid_specs.push(format!(
+                                "Package \"{}\" depends on \"{}@{}\", but there's no workspace named \"{}\"",
+                                ws.name, dep, version, name
+                            ));
+                            continue;
+                        }
+                        if let Some(local_version) = versions.get(name.as_str()) {
+                            if !Self::is_linked(&spec, ws, &paths, local_version) {
+                                unlinked.push(format!(
+                                    "Package \"{}\" depends on \"{}@{}\", which doesn't match the local \"{}@{}\". It will be installed from the registry instead of linked.",
+                                    ws.name, dep, version, name, local_version
+                                ));
+                                continue;
+                            }
+                        }
+                        name
+                    }
+                    Err(e) => {
+                        invalid_specs.push(format!(
+                            "Package \"{}\" has an unsupported dependency spec for \"{}\": {}",
+                            ws.name, dep, e
+                        ));
+                        dep.to_owned()
+                    }
+                };
+                deps.push((version.to_owned(), name));
+            }
+
+            map.insert(ws.name.clone(), (ws.package_json.version.clone(), deps));
+        }
+
+        map
+    }
+
+    /// Name of the package a dependency entry actually points at: the local
+    /// workspace for `file:`/`link:` specs that resolve to one, the aliased
+    /// package for `npm:` specs, and the dependency key otherwise.
+    fn resolve_dep_name(
+        dep: &str,
+        spec: &DepSpec,
+        ws: &Workspace,
+        paths: &HashMap<PathBuf, String>,
+    ) -> String {
+        if let Some(path) = spec.local_path(&ws.path) {
+            if let Some(name) = paths.get(Path::new(&path)) {
+                return name.to_owned();
+            }
+        }
+
+        spec.package_name(dep).to_owned()
+    }
+
+    fn is_linked(
+        spec: &DepSpec,
+        ws: &Workspace,
+        paths: &HashMap<PathBuf, String>,
+        local_version: &str,
+    ) -> bool {
+        if let Some(path) = spec.local_path(&ws.path) {
+            return paths.contains_key(&path);
+        }
+
+        match spec {
+            DepSpec::Workspace(_) => true,
+            _ => match Version::parse(local_version) {
+                Ok(version) => spec.matches(&version) == Some(true),
+                // Invalid local versions are reported by validate_versions.
+                Err(_) => true,
+            },
+        }
+    }
+
+    fn build_inversed_dep_graph(direct: &DepMap) -> DepMap {
+        let mut map: DepMap = HashMap::new();
+
+        for (ws_name, (ws_version, deps)) in direct {
+            for (version, dep) in deps {
+                let entry = map
+                    .entry(dep.clone())
+                    .or_insert_with(|| (version.clone(), vec![]));
+                entry.0 = version.clone();
+                entry.1.push((ws_version.clone(), ws_name.clone()));
+            }
+        }
+
+        map
+    }
+}
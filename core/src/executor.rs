@@ -0,0 +1,688 @@
+//! Running a `package.json` script in several workspaces at once.
+
+use super::config::Config;
+use super::dependency_graph::DepGraph;
+use super::file_cache::FileCache;
+use super::process_tree;
+use super::profiler::Profiler;
+use super::project::Project;
+use super::pty;
+use super::run_summary::{TaskStatus, TaskSummary};
+use super::workspace::Workspace;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Directory of the cache task logs are written to, one file per task.
+pub const LOGS_DIR: &str = "logs";
+
+/// Spawns happen one at a time: descriptors opened for one task must be
+/// marked close-on-exec before another task's process is forked.
+static SPAWN: Mutex<()> = Mutex::new(());
+
+/// How the output of tasks running at once is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputMode {
+    /// Lines as they come, prefixed with `[workspace:task]`.
+    #[default]
+    Stream,
+    /// All the output of a task at once, when it's done.
+    Grouped,
+    /// Only the output of failed tasks, when they're done.
+    ErrorsOnly,
+}
+
+/// What happens to the other tasks when one fails.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FailurePolicy {
+    /// Running tasks are killed and no other task starts.
+    #[default]
+    FailFast,
+    /// Only the tasks of workspaces depending on the failed one are skipped.
+    Continue,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutorOptions {
+    pub output: OutputMode,
+    /// Tasks running at once, the number of CPUs by default.
+    pub concurrency: Option<usize>,
+    pub policy: FailurePolicy,
+    /// Seconds after which tasks without a timeout of their own are
+    /// terminated.
+    pub timeout: Option<u64>,
+    /// Catch SIGINT and SIGTERM while tasks run, to terminate them before
+    /// exiting. Only for processes of our own, Node catches them itself and
+    /// calls `process_tree::interrupt`.
+    pub catch_interrupts: bool,
+}
+
+impl OutputMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "stream" => Ok(OutputMode::Stream),
+            "grouped" => Ok(OutputMode::Grouped),
+            "errors-only" => Ok(OutputMode::ErrorsOnly),
+            _ => Err(format!(
+                "Unknown output mode \"{}\", expected stream, grouped or errors-only",
+                mode
+            )),
+        }
+    }
+}
+
+/// Processes of the running tasks, so they can be terminated on failures,
+/// timeouts and interrupts.
+#[derive(Default)]
+struct Running {
+    processes: Mutex<HashMap<String, Process>>,
+    /// Process groups sent SIGTERM that may still have processes left.
+    terminating: Mutex<Vec<process_tree::Terminating>>,
+    cancelled: AtomicBool,
+}
+
+struct Process {
+    child: Child,
+    deadline: Option<Instant>,
+    /// Why we terminated the process, `TimedOut` or `Cancelled`.
+    terminated: Option<TaskStatus>,
+}
+
+impl Running {
+    /// Keeps the process of a task until it's done, terminates it right
+    /// away when the run was cancelled meanwhile.
+    fn insert(&self, label: &str, mut child: Child, timeout: Option<Duration>) {
+        let mut processes = self.lock();
+        let mut terminated = None;
+        if self.is_cancelled() {
+            self.terminate(&mut child);
+            terminated = Some(TaskStatus::Cancelled);
+        }
+        processes.insert(
+            label.to_owned(),
+            Process {
+                child,
+                deadline: timeout.map(|timeout| Instant::now() + timeout),
+                terminated,
+            },
+        );
+    }
+
+    fn remove(&self, label: &str) -> Option<Process> {
+        self.lock().remove(label)
+    }
+
+    fn cancel(&self) {
+        let mut processes = self.lock();
+        self.cancelled.store(true, Ordering::SeqCst);
+        for process in processes.values_mut() {
+            if process.terminated.is_none() {
+                process.terminated = Some(TaskStatus::Cancelled);
+                self.terminate(&mut process.child);
+            }
+        }
+    }
+
+    /// Terminates the processes past their deadline.
+    fn expire(&self) {
+        let now = Instant::now();
+        for (label, process) in self.lock().iter_mut() {
+            if process.terminated.is_none()
+                && process.deadline.is_some_and(|deadline| deadline <= now)
+            {
+                println!("Terminating {}, it ran past its timeout", label);
+                process.terminated = Some(TaskStatus::TimedOut);
+                self.terminate(&mut process.child);
+            }
+        }
+    }
+
+    fn terminate(&self, child: &mut Child) {
+        self.terminating
+            .lock()
+            .expect("[executor:running] Couldn't lock the terminated tasks")
+            .push(process_tree::terminate(child));
+    }
+
+    /// Kills what's left of terminated tasks after their grace period.
+    fn kill_leftovers(&self) {
+        self.terminating
+            .lock()
+            .expect("[executor:running] Couldn't lock the terminated tasks")
+            .retain(|terminating| !terminating.poll());
+    }
+
+    /// Waits for the processes of terminated tasks to exit, killing those
+    /// still there at the end of their grace period. Tasks are done once
+    /// their main process exits, what it started may linger.
+    fn wait_leftovers(&self) {
+        for terminating in self
+            .terminating
+            .lock()
+            .expect("[executor:running] Couldn't lock the terminated tasks")
+            .drain(..)
+        {
+            terminating.wait();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Process>> {
+        self.processes
+            .lock()
+            .expect("[executor:running] Couldn't lock the running tasks")
+    }
+}
+
+/// Tasks of a run and whether it was interrupted.
+pub struct Execution {
+    pub tasks: Vec<TaskSummary>,
+    /// The signal that interrupted the tasks, to raise again once the run
+    /// is recorded.
+    pub interrupted: Option<i32>,
+}
+
+/// Runs the script in the workspaces that have it, each once the ones it
+/// depends on are done, retrying failed ones as configured in `retries`
+/// and terminating those running longer than their timeout. What happens
+/// after a failure depends on the policy, an interrupt terminates all
+/// tasks. Returns their summaries in the order of `names`, which must be
+/// topological.
+pub fn execute(
+    project: &Project,
+    graph: &DepGraph,
+    names: &[String],
+    task: &str,
+    cache: &FileCache,
+    profiler: &Profiler,
+    options: &ExecutorOptions,
+) -> Execution {
+    let workspaces: Vec<&Workspace> = names
+        .iter()
+        .filter_map(|name| project.workspaces.get(name))
+        .filter(|ws| ws.package_json.scripts.contains_key(task))
+        .collect();
+    let selected: HashSet<&str> = workspaces.iter().map(|ws| ws.name.as_str()).collect();
+    let dependencies: HashMap<&str, Vec<String>> = workspaces
+        .iter()
+        .map(|ws| {
+            let mut deps: Vec<String> = graph
+                .dependencies(&ws.name, true)
+                .into_iter()
+                .filter(|dep| selected.contains(dep.as_str()))
+                .collect();
+            deps.sort();
+            (ws.name.as_str(), deps)
+        })
+        .collect();
+    let concurrency = options
+        .concurrency
+        .unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        })
+        .max(1);
+
+    let running = Running::default();
+    let _interrupts = options
+        .catch_interrupts
+        .then(process_tree::Interrupts::catch);
+    let mut summaries: HashMap<String, TaskSummary> = HashMap::new();
+    let mut started: HashSet<&str> = HashSet::new();
+    let mut in_flight = 0;
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| loop {
+        // Checked whether a summary or the tick woke us, a steady stream of
+        // summaries mustn't hold off timeouts and interrupts.
+        running.expire();
+        running.kill_leftovers();
+        if process_tree::interrupted().is_some() && !running.is_cancelled() {
+            println!("Interrupted, terminating the running tasks");
+            running.cancel();
+        }
+
+        let mut progressed = !running.is_cancelled();
+        while progressed {
+            progressed = false;
+            for ws in &workspaces {
+                if started.contains(ws.name.as_str()) {
+                    continue;
+                }
+                let deps = &dependencies[ws.name.as_str()];
+                if deps.iter().any(|dep| !summaries.contains_key(dep)) {
+                    continue;
+                }
+
+                if let Some(dep) = deps
+                    .iter()
+                    .find(|dep| summaries[*dep].status != TaskStatus::Succeeded)
+                {
+                    println!(
+                        "Skipping {}:{}, {}:{} didn't succeed",
+                        ws.name, task, dep, task
+                    );
+                    started.insert(&ws.name);
+                    let mut summary = unfinished(ws, task, TaskStatus::Skipped);
+                    summary.blocked_by = Some(dep.clone());
+                    summaries.insert(ws.name.clone(), summary);
+                    progressed = true;
+                } else if in_flight < concurrency {
+                    started.insert(&ws.name);
+                    in_flight += 1;
+                    let limits = Limits {
+                        retries: configured(project, ws, |config| config.retries.get(task))
+                            .unwrap_or(0),
+                        timeout: configured(project, ws, |config| config.timeouts.get(task))
+                            .or(options.timeout)
+                            .map(Duration::from_secs),
+                    };
+                    let sender = sender.clone();
+                    let running = &running;
+                    scope.spawn(move || {
+                        let summary = run_task(
+                            project,
+                            ws,
+                            task,
+                            limits,
+                            cache,
+                            profiler,
+                            options.output,
+                            running,
+                        );
+                        let _ = sender.send(summary);
+                    });
+                }
+            }
+        }
+
+        if in_flight == 0 {
+            break;
+        }
+        let summary = match receiver.recv_timeout(TICK) {
+            Ok(summary) => summary,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => {
+                panic!("[executor:execute] A task thread exited without a summary")
+            }
+        };
+        in_flight -= 1;
+        if (summary.status == TaskStatus::Failed || summary.status == TaskStatus::TimedOut)
+            && options.policy == FailurePolicy::FailFast
+            && !running.is_cancelled()
+        {
+            println!(
+                "Stopping the other tasks, {}:{} didn't succeed",
+                summary.workspace, task
+            );
+            running.cancel();
+        }
+        summaries.insert(summary.workspace.clone(), summary);
+    });
+    running.wait_leftovers();
+
+    let summaries: Vec<TaskSummary> = workspaces
+        .iter()
+        .map(|ws| {
+            summaries
+                .remove(&ws.name)
+                .unwrap_or_else(|| unfinished(ws, task, TaskStatus::Cancelled))
+        })
+        .collect();
+    let count = |status| summaries.iter().filter(|s| s.status == status).count();
+    let retried = summaries
+        .iter()
+        .filter(|s| s.status == TaskStatus::Succeeded && s.attempts > 1)
+        .count();
+    println!(
+        "Tasks: {} succeeded ({} after retries), {} failed, {} timed out, {} skipped, {} cancelled",
+        count(TaskStatus::Succeeded),
+        retried,
+        count(TaskStatus::Failed),
+        count(TaskStatus::TimedOut),
+        count(TaskStatus::Skipped),
+        count(TaskStatus::Cancelled)
+    );
+
+    Execution {
+        tasks: summaries,
+        interrupted: process_tree::interrupted(),
+    }
+}
+
+/// How often running tasks are checked for timeouts and interrupts.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Retries and timeout of a task.
+#[derive(Clone, Copy)]
+struct Limits {
+    retries: u32,
+    timeout: Option<Duration>,
+}
+
+/// A per-task setting from the config of the workspace, or else the root
+/// one.
+fn configured<'a, T: Copy + 'a>(
+    project: &'a Project,
+    ws: &'a Workspace,
+    get: impl Fn(&'a Config) -> Option<&'a T>,
+) -> Option<T> {
+    get(&ws.package_json.config)
+        .or_else(|| get(project.config()))
+        .copied()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_task(
+    project: &Project,
+    ws: &Workspace,
+    task: &str,
+    limits: Limits,
+    cache: &FileCache,
+    profiler: &Profiler,
+    output: OutputMode,
+    running: &Running,
+) -> TaskSummary {
+    let label = format!("{}:{}", ws.name, task);
+    profiler.name_lane(&format!("task {}", label));
+    let _span = profiler.span(&label, "task");
+    let start = Instant::now();
+
+    let log_name = format!(
+        "{}/{}/{}.log",
+        LOGS_DIR,
+        ws.name,
+        task.replace([':', '/', '\\'], "-")
+    );
+    let mut log = match cache.create(&log_name) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            println!("Couldn't create the log of {}: {}", label, e);
+            None
+        }
+    };
+
+    let prefix = format!("[{}] ", label);
+    let mut captured = vec![];
+    let mut attempts = 0;
+    let outcome = loop {
+        attempts += 1;
+        if attempts > 1 {
+            let notice = vec![format!(
+                "Retrying {} (attempt {} of {})",
+                label,
+                attempts,
+                limits.retries + 1
+            )];
+            write_lines(&mut log, &notice);
+            if output == OutputMode::Stream {
+                print_lines(&notice, None);
+            } else {
+                captured.extend(notice);
+            }
+        }
+
+        let (exit_code, terminated) = match run_attempt(project, ws, task, limits, running) {
+            Ok(chunks) => {
+                for lines in chunks {
+                    write_lines(&mut log, &lines);
+                    if output == OutputMode::Stream {
+                        print_lines(&lines, Some(&prefix));
+                    } else {
+                        captured.extend(lines);
+                    }
+                }
+
+                match running.remove(&label) {
+                    Some(mut process) => match process.child.wait() {
+                        Ok(status) => (status.code(), process.terminated),
+                        Err(e) => {
+                            println!("Couldn't wait for {}: {}", label, e);
+                            (None, process.terminated)
+                        }
+                    },
+                    None => (None, None),
+                }
+            }
+            Err(e) => {
+                println!("Couldn't start {}: {}", label, e);
+                (None, None)
+            }
+        };
+
+        if exit_code == Some(0) || attempts > limits.retries || running.is_cancelled() {
+            break (exit_code, terminated);
+        }
+    };
+    let (exit_code, terminated) = outcome;
+
+    // Tasks that failed or timed out on their own before the run was
+    // cancelled are reported as such, not as cancelled.
+    let status = match exit_code {
+        Some(0) => TaskStatus::Succeeded,
+        _ => terminated.unwrap_or(TaskStatus::Failed),
+    };
+
+    let failed = status == TaskStatus::Failed || status == TaskStatus::TimedOut;
+    if output == OutputMode::Grouped || (output == OutputMode::ErrorsOnly && failed) {
+        let mut block = vec![format!("> {}", label)];
+        block.extend(captured);
+        print_lines(&block, None);
+    }
+    let log_file = log.map(|_| cache.get_cache_file_path(&log_name));
+    if failed {
+        let exit = match (status, exit_code) {
+            (TaskStatus::TimedOut, _) => format!(
+                "timed out after {}s",
+                limits.timeout.unwrap_or_default().as_secs()
+            ),
+            (_, Some(code)) => format!("exited with {}", code),
+            (_, None) => "was killed".to_owned(),
+        };
+        match &log_file {
+            Some(path) => println!("{} {}, log: {}", label, exit, path),
+            None => println!("{} {}", label, exit),
+        }
+    }
+
+    TaskSummary {
+        workspace: ws.name.clone(),
+        task: task.to_owned(),
+        status,
+        duration: start.elapsed().as_millis(),
+        exit_code,
+        cache_hit: false,
+        log_file,
+        attempts,
+        blocked_by: None,
+    }
+}
+
+/// Starts one attempt of the task, unless the run was cancelled. Its
+/// process is kept in `running` until the output ends.
+fn run_attempt(
+    project: &Project,
+    ws: &Workspace,
+    task: &str,
+    limits: Limits,
+    running: &Running,
+) -> io::Result<Receiver<Vec<String>>> {
+    if running.is_cancelled() {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "the run was cancelled",
+        ));
+    }
+
+    let script = &ws.package_json.scripts[task];
+    let (child, chunks) = spawn(project, ws, task, script)?;
+    running.insert(&format!("{}:{}", ws.name, task), child, limits.timeout);
+    Ok(chunks)
+}
+
+/// The summary of a task that didn't run.
+fn unfinished(ws: &Workspace, task: &str, status: TaskStatus) -> TaskSummary {
+    TaskSummary {
+        workspace: ws.name.clone(),
+        task: task.to_owned(),
+        status,
+        duration: 0,
+        exit_code: None,
+        cache_hit: false,
+        log_file: None,
+        attempts: 0,
+        blocked_by: None,
+    }
+}
+
+/// Starts the script the way package managers do, in the workspace with
+/// binaries of the workspace and root `node_modules` on the `PATH`. Output
+/// goes to a pseudo-terminal, or to pipes where there's none, and comes
+/// out of the receiver in complete lines until the process closes it.
+fn spawn(
+    project: &Project,
+    ws: &Workspace,
+    task: &str,
+    script: &str,
+) -> io::Result<(Child, Receiver<Vec<String>>)> {
+    let mut paths = vec![
+        Path::new(&ws.path).join("node_modules").join(".bin"),
+        Path::new(&project.path).join("node_modules").join(".bin"),
+    ];
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&path));
+    }
+    let path = std::env::join_paths(paths).map_err(io::Error::other)?;
+
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(script);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    };
+    command
+        .current_dir(&ws.path)
+        .env("PATH", path)
+        .env("npm_lifecycle_event", task)
+        .env("npm_package_name", &ws.name)
+        .env("npm_package_version", &ws.package_json.version)
+        .stdin(Stdio::null());
+    process_tree::isolate(&mut command);
+
+    let (sender, receiver) = mpsc::channel();
+    let _guard = SPAWN
+        .lock()
+        .expect("[executor:spawn] Couldn't lock the spawn mutex");
+    match pty::open() {
+        Ok(pty) => {
+            command.stdout(pty.slave.try_clone()?).stderr(pty.slave);
+            let child = command.spawn()?;
+            // Our copies of the slave go with the command, so reading the
+            // master ends once the process and its children close theirs.
+            drop(command);
+            forward(Box::new(pty.master), sender);
+            Ok((child, receiver))
+        }
+        Err(_) => {
+            let mut child = command
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            if let Some(stdout) = child.stdout.take() {
+                forward(Box::new(stdout), sender.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forward(Box::new(stderr), sender);
+            }
+            Ok((child, receiver))
+        }
+    }
+}
+
+/// Sends the lines read until the end or an error, which is how a
+/// pseudo-terminal master reports the slave being closed on Linux. Each
+/// stream has its own buffer, so stdout and stderr lines don't mix.
+fn forward(mut reader: Box<dyn Read + Send>, sender: mpsc::Sender<Vec<String>>) {
+    thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        let mut lines = Lines::default();
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let complete = lines.push(&buffer[..n]);
+                    if !complete.is_empty() && sender.send(complete).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = sender.send(lines.finish());
+    });
+}
+
+/// Splits output into lines, without the carriage returns terminals add.
+#[derive(Default)]
+struct Lines {
+    pending: Vec<u8>,
+}
+
+impl Lines {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = vec![];
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(to_line(&line[..end]));
+        }
+        lines
+    }
+
+    fn finish(self) -> Vec<String> {
+        if self.pending.is_empty() {
+            vec![]
+        } else {
+            vec![to_line(&self.pending)]
+        }
+    }
+}
+
+fn to_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\r')
+        .to_owned()
+}
+
+fn write_lines(log: &mut Option<File>, lines: &[String]) {
+    if let Some(file) = log {
+        for line in lines {
+            if writeln!(file, "{}", line).is_err() {
+                *log = None;
+                return;
+            }
+        }
+    }
+}
+
+/// Holds stdout for all the lines, so those of other tasks don't interleave.
+fn print_lines(lines: &[String], prefix: Option<&str>) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in lines {
+        let _ = writeln!(stdout, "{}{}", prefix.unwrap_or_default(), line);
+    }
+}
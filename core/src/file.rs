@@ -0,0 +1,146 @@
+//! Input files of a workspace, fingerprinted by content hash.
+
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Coarsest timestamp granularity of the file systems we run on (FAT has
+/// 2 seconds). A file modified within it before being hashed may change
+/// again without its mtime changing.
+const RACY_WINDOW: u128 = 2_000_000_000;
+
+/// Files at least this large are memory-mapped instead of read into a
+/// buffer.
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct File {
+    pub path: String,
+    /// Git blob id of the contents, so hashes can be seeded from the index.
+    pub hash: String,
+    /// Taken before hashing. `None` in caches written before stats were
+    /// recorded, which makes the file hashed again.
+    #[serde(default)]
+    pub stat: Option<Stat>,
+    /// When the file was hashed, in nanoseconds since the epoch.
+    #[serde(default)]
+    pub hashed_at: u128,
+}
+
+/// What git's index compares to tell if a file may have changed. Inode and
+/// ctime are 0 where the platform doesn't have them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Stat {
+    pub size: u64,
+    pub inode: u64,
+    /// Nanoseconds since the epoch.
+    pub mtime: u128,
+    /// Nanoseconds since the epoch.
+    pub ctime: u128,
+}
+
+impl Stat {
+    pub fn read(path: &str) -> std::io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+
+        #[cfg(unix)]
+        let (inode, ctime) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                metadata.ino(),
+                metadata.ctime() as u128 * 1_000_000_000 + metadata.ctime_nsec() as u128,
+            )
+        };
+        #[cfg(not(unix))]
+        let (inode, ctime) = (0, 0);
+
+        Ok(Self {
+            size: metadata.len(),
+            inode,
+            mtime,
+            ctime,
+        })
+    }
+}
+
+/// Files are read and hashed synchronously, on the hashing pool.
+impl File {
+    pub fn new(path: String) -> std::io::Result<Self> {
+        let stat = Stat::read(&path)?;
+        let mut file = std::fs::File::open(&path)?;
+        let hash = if stat.size >= MMAP_THRESHOLD {
+            // Safety: the mapping only lives while hashing, a file truncated
+            // meanwhile by another process is the only way to fault it.
+            let content = unsafe { Mmap::map(&file)? };
+            blob_hash(&content)
+        } else {
+            let mut content = Vec::with_capacity(stat.size as usize);
+            file.read_to_end(&mut content)?;
+            blob_hash(&content)
+        };
+
+        Ok(Self {
+            hash,
+            path,
+            stat: Some(stat),
+            hashed_at: now(),
+        })
+    }
+
+    /// Takes the hash from the git index instead of reading the file, which
+    /// must be unmodified since it was added there.
+    pub fn with_hash(path: String, hash: String) -> std::io::Result<Self> {
+        let stat = Stat::read(&path)?;
+
+        Ok(Self {
+            hash,
+            path,
+            stat: Some(stat),
+            hashed_at: now(),
+        })
+    }
+
+    /// Skips hashing when the stat matches and the file isn't racily clean,
+    /// i.e. it wasn't modified so shortly before being hashed that a later
+    /// change could have kept the same mtime. Racily clean files are always
+    /// hashed again, after which they usually aren't racy anymore.
+    pub fn invalidate(&self) -> std::io::Result<(bool, Self)> {
+        let stat = Stat::read(&self.path)?;
+        match &self.stat {
+            Some(old) if *old == stat && !self.is_racy() => Ok((false, self.clone())),
+            _ => {
+                let new_file = Self::new(self.path.to_owned())?;
+                Ok((new_file.hash != self.hash, new_file))
+            }
+        }
+    }
+
+    fn is_racy(&self) -> bool {
+        match &self.stat {
+            Some(stat) => stat.mtime + RACY_WINDOW >= self.hashed_at,
+            None => true,
+        }
+    }
+}
+
+/// Same as `git hash-object`.
+pub fn blob_hash(content: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.input(format!("blob {}\0", content.len()).as_bytes());
+    hasher.input(content);
+    format!("{:x}", hasher.result())
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+//! Files persisted between runs under the project's `.cache`.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+/// A directory of text files, written with their parent directories.
+#[derive(Debug)]
+pub struct FileCache {
+    path: String,
+}
+
+impl FileCache {
+    pub fn new(path: String) -> FileCache {
+        FileCache { path }
+    }
+
+    pub fn read(&self, file_name: &str) -> std::io::Result<String> {
+        let mut file = File::open(self.get_cache_file_path(file_name))?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        Ok(contents)
+    }
+
+    pub fn write(&self, file_name: &str, content: &str) -> std::io::Result<()> {
+        let file_path = self.get_cache_file_path(file_name);
+        if let Some(parent) = Path::new(&file_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(file_path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Creates or truncates a file to write gradually, e.g. a task log.
+    pub fn create(&self, file_name: &str) -> std::io::Result<File> {
+        let file_path = self.get_cache_file_path(file_name);
+        if let Some(parent) = Path::new(&file_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        File::create(file_path)
+    }
+
+    pub fn has(&self, file_name: &str) -> bool {
+        Path::new(&self.get_cache_file_path(file_name)).exists()
+    }
+
+    pub fn get_cache_file_path(&self, file_name: &str) -> String {
+        format!("{}{}{}", self.path, std::path::MAIN_SEPARATOR, file_name)
+    }
+}
@@ -0,0 +1,231 @@
+//! pnpm-style filters scoping a run to some of the workspaces.
+
+use super::dependency_graph::DepGraph;
+use super::workspace::Workspace;
+use glob::{MatchOptions, Pattern};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+/// A single pnpm-style filter expression, e.g. `@scope/*`, `app...`,
+/// `...lib`, `./packages/ui/**`, `[origin/main]` or `!name`.
+#[derive(Debug)]
+pub struct Filter {
+    expression: String,
+    exclude: bool,
+    selector: Selector,
+    with_dependencies: bool,
+    with_dependents: bool,
+    include_self: bool,
+}
+
+#[derive(Debug)]
+enum Selector {
+    Name(Pattern),
+    Directory(Pattern),
+    ChangedSince(String),
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut rest = expression.trim();
+        let exclude = rest.starts_with('!');
+        if exclude {
+            rest = &rest[1..];
+        }
+
+        let mut with_dependents = false;
+        let mut with_dependencies = false;
+        let mut include_self = true;
+
+        if let Some(stripped) = rest.strip_prefix("...") {
+            with_dependents = true;
+            rest = stripped;
+            if let Some(stripped) = rest.strip_prefix('^') {
+                include_self = false;
+                rest = stripped;
+            }
+        }
+
+        if let Some(stripped) = rest.strip_suffix("...") {
+            with_dependencies = true;
+            rest = stripped;
+            if let Some(stripped) = rest.strip_suffix('^') {
+                include_self = false;
+                rest = stripped;
+            }
+        }
+
+        if rest.is_empty() {
+            return Err(format!("Invalid filter \"{}\": empty selector", expression));
+        }
+
+        let selector = if rest.starts_with('[') && rest.ends_with(']') {
+            Selector::ChangedSince(rest[1..rest.len() - 1].to_owned())
+        } else if rest.starts_with('.') || rest.starts_with('/') || rest.starts_with('{') {
+            let dir = rest.trim_start_matches('{').trim_end_matches('}');
+            let dir = dir.trim_start_matches("./").trim_end_matches('/');
+            Selector::Directory(parse_pattern(expression, dir)?)
+        } else {
+            Selector::Name(parse_pattern(expression, rest)?)
+        };
+
+        Ok(Self {
+            expression: expression.to_owned(),
+            exclude,
+            selector,
+            with_dependencies,
+            with_dependents,
+            include_self,
+        })
+    }
+
+    fn select(
+        &self,
+        graph: &DepGraph,
+        workspaces: &HashMap<String, Workspace>,
+        root: &str,
+    ) -> Result<HashSet<String>, String> {
+        let matched: HashSet<String> = match &self.selector {
+            Selector::Name(pattern) => workspaces
+                .keys()
+                .filter(|name| pattern.matches_with(name, match_options()))
+                .cloned()
+                .collect(),
+            Selector::Directory(pattern) => workspaces
+                .values()
+                .filter(|ws| {
+                    let rel = relative_path(root, &ws.path);
+                    pattern.matches_with(&rel, match_options())
+                        || pattern.matches_with(&format!("{}/package.json", rel), match_options())
+                })
+                .map(|ws| ws.name.clone())
+                .collect(),
+            Selector::ChangedSince(git_ref) => {
+                let changed = changed_files_since(root, git_ref)?;
+                // A file belongs to the deepest workspace it's in, files
+                // outside the others to the root one.
+                let ws_paths: Vec<(String, &String)> = workspaces
+                    .values()
+                    .map(|ws| (relative_path(root, &ws.path), &ws.name))
+                    .collect();
+                changed
+                    .iter()
+                    .filter_map(|file| {
+                        ws_paths
+                            .iter()
+                            .filter(|(rel, _)| {
+                                rel.is_empty() || file.starts_with(&format!("{}/", rel))
+                            })
+                            .max_by_key(|(rel, _)| rel.len())
+                            .map(|(_, name)| (*name).clone())
+                    })
+                    .collect()
+            }
+        };
+
+        let mut selected = HashSet::new();
+        for name in &matched {
+            if self.include_self {
+                selected.insert(name.clone());
+            }
+            if self.with_dependencies {
+                selected.extend(graph.dependencies(name, true));
+            }
+            if self.with_dependents {
+                selected.extend(graph.dependents(name, true));
+            }
+        }
+
+        Ok(selected)
+    }
+}
+
+/// Resolves a list of filter expressions to a topologically ordered list of
+/// workspaces. Include filters are unioned, exclude filters are subtracted.
+/// No include filters means every workspace is selected.
+pub fn filter_workspaces(
+    expressions: &[String],
+    graph: &DepGraph,
+    workspaces: &HashMap<String, Workspace>,
+    root: &str,
+) -> Result<Vec<String>, String> {
+    let filters = expressions
+        .iter()
+        .map(|expr| Filter::parse(expr))
+        .collect::<Result<Vec<Filter>, String>>()?;
+
+    let mut included: HashSet<String> = HashSet::new();
+    let mut excluded: HashSet<String> = HashSet::new();
+    let mut has_includes = false;
+
+    for filter in &filters {
+        let selected = filter.select(graph, workspaces, root)?;
+        if selected.is_empty() {
+            eprintln!(
+                "Filter \"{}\" didn't match any workspaces",
+                filter.expression
+            );
+        }
+        if filter.exclude {
+            excluded.extend(selected);
+        } else {
+            has_includes = true;
+            included.extend(selected);
+        }
+    }
+
+    if !has_includes {
+        included = workspaces.keys().cloned().collect();
+    }
+
+    graph.top_sort(included.difference(&excluded).cloned().collect())
+}
+
+fn parse_pattern(expression: &str, pattern: &str) -> Result<Pattern, String> {
+    Pattern::new(pattern).map_err(|e| format!("Invalid filter \"{}\": {}", expression, e))
+}
+
+fn match_options() -> MatchOptions {
+    MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    }
+}
+
+fn relative_path(root: &str, path: &str) -> String {
+    Path::new(path)
+        .strip_prefix(root)
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| path.to_owned())
+}
+
+/// Files changed since the ref, committed or not, and untracked ones that
+/// aren't ignored. Paths are relative to the root.
+fn changed_files_since(root: &str, git_ref: &str) -> Result<Vec<String>, String> {
+    let mut files = git_lines(root, &["diff", "--name-only", "--relative", git_ref])
+        .map_err(|e| format!("git diff against \"{}\" failed: {}", git_ref, e))?;
+    files.extend(
+        git_lines(root, &["ls-files", "--others", "--exclude-standard"])
+            .map_err(|e| format!("git ls-files failed: {}", e))?,
+    );
+    Ok(files)
+}
+
+fn git_lines(root: &str, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("Couldn't run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_owned())
+        .collect())
+}
@@ -0,0 +1,65 @@
+//! Blob ids of files in the git index, to seed hashes without reading files.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+/// Regular files tracked by git and unmodified in the working tree, by
+/// absolute path. Their blob id is what `File` would hash them to, unless
+/// `.gitattributes` filters rewrite them on checkout, which costs one
+/// extra change when they're modified for the first time.
+#[derive(Debug, Default)]
+pub struct GitIndex {
+    ids: HashMap<String, String>,
+}
+
+impl GitIndex {
+    pub fn read(root: &str) -> Result<Self, String> {
+        let staged = git(root, &["ls-files", "-s", "-z"])?;
+        let modified = git(root, &["ls-files", "-m", "-z"])?;
+        let modified: HashSet<&str> = modified.split('\0').collect();
+
+        let mut ids = HashMap::new();
+        for entry in staged.split('\0').filter(|entry| !entry.is_empty()) {
+            // <mode> <object> <stage>\t<path>
+            let (info, path) = match entry.split_once('\t') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let fields: Vec<&str> = info.split(' ').collect();
+            match fields.as_slice() {
+                [mode, id, "0"] if (*mode == "100644" || *mode == "100755") => {
+                    if !modified.contains(&path) {
+                        let path = Path::new(root).join(path).to_string_lossy().into_owned();
+                        ids.insert(path, (*id).to_owned());
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        Ok(Self { ids })
+    }
+
+    pub fn object_id(&self, path: &str) -> Option<&str> {
+        self.ids.get(path).map(String::as_str)
+    }
+}
+
+fn git(root: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("Couldn't run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,75 @@
+//! Dependency graph queries with JSON answers, for the bindings and the CLI.
+
+use super::dependency_graph::DepGraph;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Questions JS tooling can ask about the dependency graph, answered from
+/// the graph of the last run.
+#[derive(Debug, Clone)]
+pub enum GraphQuery {
+    Dependencies {
+        name: String,
+        transitive: bool,
+    },
+    Dependents {
+        name: String,
+        transitive: bool,
+    },
+    /// Every workspace, dependencies first.
+    TopologicalOrder,
+    /// Shortest chain of dependencies leading from one workspace to another.
+    Path {
+        from: String,
+        to: String,
+    },
+    /// Workspaces affected by changes in the given ones, dependencies first.
+    Affected(Vec<String>),
+}
+
+impl GraphQuery {
+    /// Lists of workspace names, or `null` for a path that doesn't exist.
+    /// Fails on unknown workspaces.
+    pub fn execute(&self, graph: &DepGraph) -> Result<Value, String> {
+        match self {
+            GraphQuery::Dependencies { name, transitive } => {
+                ensure_workspace(graph, name)?;
+                to_value(sorted(graph.dependencies(name, *transitive)))
+            }
+            GraphQuery::Dependents { name, transitive } => {
+                ensure_workspace(graph, name)?;
+                to_value(sorted(graph.dependents(name, *transitive)))
+            }
+            GraphQuery::TopologicalOrder => to_value(graph.top_sort(graph.workspace_names())?),
+            GraphQuery::Path { from, to } => {
+                ensure_workspace(graph, from)?;
+                ensure_workspace(graph, to)?;
+                to_value(graph.path(from, to))
+            }
+            GraphQuery::Affected(names) => {
+                for name in names {
+                    ensure_workspace(graph, name)?;
+                }
+                to_value(graph.get_affected(names.to_owned())?)
+            }
+        }
+    }
+}
+
+fn ensure_workspace(graph: &DepGraph, name: &str) -> Result<(), String> {
+    if graph.has_workspace(name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown workspace \"{}\"", name))
+    }
+}
+
+fn sorted(names: HashSet<String>) -> Vec<String> {
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort();
+    names
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
@@ -0,0 +1,54 @@
+//! The worker pool input files are hashed on.
+
+use futures::channel::oneshot;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::future::Future;
+
+/// A pool of its own, so reading and hashing don't compete with the executor
+/// waiting on resolvers. Workers read one file at a time, so at most as many
+/// files as there are threads are open at once, whatever the number of
+/// inputs.
+pub struct Hasher {
+    pool: ThreadPool,
+}
+
+impl Hasher {
+    /// `threads` defaults to the available parallelism.
+    pub fn new(threads: Option<usize>) -> Result<Self, String> {
+        let threads = threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        });
+
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("sculpture-hash-{}", i))
+            .build()
+            .map(|pool| Self { pool })
+            .map_err(|e| format!("Couldn't start the hashing pool: {}", e))
+    }
+
+    /// Runs `f` on every item on the pool, resolves with the results in the
+    /// order of the items. Batches of several callers share the workers.
+    pub fn map<T, R, F>(&self, items: Vec<T>, f: F) -> impl Future<Output = Vec<R>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.pool.spawn(move || {
+            let results: Vec<R> = items.into_par_iter().map(f).collect();
+            // The caller may have stopped waiting, nothing to do about it.
+            let _ = sender.send(results);
+        });
+
+        async move {
+            receiver
+                .await
+                .expect("[hasher:map] A hashing batch was dropped")
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Core of sculpture, a monorepo orchestrator for JS workspaces, usable
+//! without Node.
+//!
+//! - [`Project`] discovers the workspaces of a repository from the
+//!   `workspaces` field of its root `package.json`.
+//! - [`Project::invalidate`] hashes the inputs of every workspace, resolved
+//!   by the [`ResolverRegistry`], and reports the ones that changed.
+//! - [`DepGraph`] links workspaces by their dependencies, validates them and
+//!   answers which workspaces are affected and in which order they can run.
+//! - [`run`] puts it together the way the `scu` CLI does, persisting the
+//!   project and a [`RunSummary`] in a [`FileCache`].
+//!
+//! ```no_run
+//! use sculpture_core::{DepGraph, Project};
+//!
+//! let project = Project::new("/path/to/repo".to_owned());
+//! let graph = DepGraph::new(project.workspaces.values().cloned().collect());
+//! let order = graph.top_sort(graph.workspace_names()).unwrap();
+//! ```
+
+#![deny(clippy::all)]
+
+pub mod boundaries;
+pub mod changelog;
+pub mod changeset;
+pub mod config;
+pub mod dep_spec;
+pub mod dependency_graph;
+pub mod executor;
+pub mod file;
+pub mod file_cache;
+pub mod filter;
+pub mod git_index;
+pub mod graph_query;
+pub mod hasher;
+pub mod lockfile;
+pub mod package_json;
+pub mod paths;
+pub mod process_tree;
+pub mod profiler;
+pub mod project;
+pub mod prune;
+pub mod pty;
+pub mod publish;
+pub mod release;
+pub mod resolvers;
+pub mod run_summary;
+pub mod runner;
+pub mod version_policy;
+pub mod why;
+pub mod workspace;
+
+pub use config::Config;
+pub use dependency_graph::DepGraph;
+pub use file_cache::FileCache;
+pub use graph_query::GraphQuery;
+pub use package_json::PackageJson;
+pub use profiler::Profiler;
+pub use project::Project;
+pub use prune::PruneOptions;
+pub use publish::{PublishOptions, PublishResult};
+pub use release::Release;
+pub use resolvers::{InputResolver, ResolverRegistry};
+pub use run_summary::RunSummary;
+pub use runner::{
+    prune_project, publish_project, query_affected, query_graph, run, version_project, RunOptions,
+};
+pub use workspace::Workspace;
@@ -0,0 +1,157 @@
+//! Reading and pruning npm, yarn and pnpm lockfiles.
+
+mod package_lock;
+mod pnpm;
+mod yarn;
+
+use super::package_json::PackageJson;
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// Lockfiles in the order they're looked up in the project root.
+const LOCKFILES: &[&str] = &["pnpm-lock.yaml", "yarn.lock", "package-lock.json"];
+
+/// A lockfile normalized to a graph of resolved external packages.
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    pub path: String,
+    /// Package id -> resolved package. Ids are format specific and only
+    /// meaningful within a single lockfile.
+    packages: HashMap<String, LockPackage>,
+    /// Workspace path relative to the root -> ids of its direct external
+    /// dependencies. Formats that don't record workspaces (yarn classic)
+    /// fall back to `descriptors`.
+    importers: HashMap<String, Vec<String>>,
+    /// `name@range` -> package id, for formats keyed by descriptors.
+    descriptors: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LockPackage {
+    pub name: String,
+    pub version: String,
+    /// Integrity hash or resolved url, whatever the lockfile records.
+    pub checksum: String,
+    pub dependencies: Vec<String>,
+}
+
+impl Lockfile {
+    /// Finds and parses the lockfile in the project root, if there is one.
+    pub fn detect(root: &str) -> Option<Result<Self, String>> {
+        LOCKFILES.iter().find_map(|name| {
+            let path = Path::new(root).join(name);
+            if path.exists() {
+                Some(Self::read(&path.to_string_lossy()))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn read(path: &str) -> Result<Self, String> {
+        let contents = read_contents(path)?;
+
+        let mut lockfile = if path.ends_with("package-lock.json") {
+            package_lock::parse(&contents)
+        } else if path.ends_with("yarn.lock") {
+            yarn::parse(&contents)
+        } else if path.ends_with("pnpm-lock.yaml") {
+            pnpm::parse(&contents)
+        } else {
+            Err("unknown lockfile format".to_owned())
+        }
+        .map_err(|e| format!("Couldn't parse {}: {}", path, e))?;
+
+        lockfile.path = path.to_owned();
+        Ok(lockfile)
+    }
+
+    pub fn file_name(&self) -> &str {
+        Path::new(&self.path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    /// Ids of every external package a workspace resolves, directly or
+    /// transitively.
+    pub fn closure(&self, ws_path: &str, package_json: &PackageJson) -> BTreeSet<String> {
+        let mut roots: Vec<String> = self.importers.get(ws_path).cloned().unwrap_or_default();
+        if roots.is_empty() {
+            let dependencies = package_json
+                .dependencies
+                .iter()
+                .chain(package_json.dev_dependencies.iter());
+            for (name, range) in dependencies {
+                if let Some(id) = self.resolve_descriptor(name, range) {
+                    roots.push(id.to_owned());
+                }
+            }
+        }
+
+        let mut closure = BTreeSet::new();
+        let mut queue = VecDeque::from(roots);
+        while let Some(id) = queue.pop_front() {
+            if !closure.insert(id.clone()) {
+                continue;
+            }
+            if let Some(package) = self.packages.get(&id) {
+                queue.extend(package.dependencies.iter().cloned());
+            }
+        }
+
+        closure.retain(|id| self.packages.contains_key(id));
+        closure
+    }
+
+    /// Fingerprint of a workspace's transitive external dependencies.
+    pub fn workspace_hash(&self, ws_path: &str, package_json: &PackageJson) -> String {
+        let mut entries: Vec<String> = self
+            .closure(ws_path, package_json)
+            .iter()
+            .filter_map(|id| self.packages.get(id))
+            .map(|p| format!("{}@{} {}", p.name, p.version, p.checksum))
+            .collect();
+        entries.sort();
+        entries.dedup();
+
+        format!("{:x}", Sha1::digest_str(&entries.join("\n")))
+    }
+
+    /// Lockfile contents with only the entries the given workspaces (path
+    /// relative to the root and manifest) need, in the original format.
+    pub fn prune(&self, workspaces: &[(String, &PackageJson)]) -> Result<String, String> {
+        let mut keep = BTreeSet::new();
+        for (ws_path, package_json) in workspaces {
+            keep.extend(self.closure(ws_path, package_json));
+        }
+        let ws_paths: Vec<String> = workspaces.iter().map(|(path, _)| path.clone()).collect();
+
+        let contents = read_contents(&self.path)?;
+        match self.file_name() {
+            "package-lock.json" => package_lock::prune(&contents, &keep, &ws_paths),
+            "yarn.lock" => yarn::prune(&contents, &keep, &ws_paths),
+            "pnpm-lock.yaml" => pnpm::prune(&contents, &keep, &ws_paths),
+            _ => Err("unknown lockfile format".to_owned()),
+        }
+        .map_err(|e| format!("Couldn't prune {}: {}", self.path, e))
+    }
+
+    fn resolve_descriptor(&self, name: &str, range: &str) -> Option<&String> {
+        self.descriptors
+            .get(&format!("{}@{}", name, range))
+            .or_else(|| self.descriptors.get(&format!("{}@npm:{}", name, range)))
+    }
+}
+
+fn read_contents(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))
+}
+
+/// Splits `@scope/name@range` into name and range.
+fn split_descriptor(descriptor: &str) -> Option<(&str, &str)> {
+    let idx = descriptor.get(1..)?.find('@')? + 1;
+    Some((&descriptor[..idx], &descriptor[idx + 1..]))
+}
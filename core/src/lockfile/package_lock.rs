@@ -0,0 +1,171 @@
+use super::{LockPackage, Lockfile};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageLock {
+    #[serde(default)]
+    lockfile_version: u32,
+
+    #[serde(default)]
+    packages: HashMap<String, Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(default)]
+    version: String,
+
+    #[serde(default)]
+    resolved: String,
+
+    #[serde(default)]
+    integrity: String,
+
+    #[serde(default)]
+    link: bool,
+
+    #[serde(default)]
+    dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    optional_dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    dev_dependencies: HashMap<String, String>,
+}
+
+/// package-lock.json v2 and v3. Both have a flat `packages` map keyed by
+/// install location, so a dependency is resolved the way node does: the
+/// closest `node_modules` walking up from the dependent.
+pub fn parse(contents: &str) -> Result<Lockfile, String> {
+    let lock: PackageLock = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    if lock.lockfile_version < 2 {
+        return Err(format!(
+            "lockfileVersion {} is not supported, run npm >= 7 to upgrade it",
+            lock.lockfile_version
+        ));
+    }
+
+    let mut lockfile = Lockfile::default();
+
+    for (location, entry) in &lock.packages {
+        if !location.contains("node_modules/") || entry.link {
+            continue;
+        }
+
+        let deps = entry
+            .dependencies
+            .keys()
+            .chain(entry.optional_dependencies.keys())
+            .filter_map(|dep| resolve(&lock.packages, location, dep))
+            .collect();
+
+        lockfile.packages.insert(
+            location.clone(),
+            LockPackage {
+                name: package_name(location).to_owned(),
+                version: entry.version.clone(),
+                checksum: if entry.integrity.is_empty() {
+                    entry.resolved.clone()
+                } else {
+                    entry.integrity.clone()
+                },
+                dependencies: deps,
+            },
+        );
+    }
+
+    for (location, entry) in &lock.packages {
+        if location.contains("node_modules/") {
+            continue;
+        }
+
+        let deps = entry
+            .dependencies
+            .keys()
+            .chain(entry.optional_dependencies.keys())
+            .chain(entry.dev_dependencies.keys())
+            .filter_map(|dep| resolve(&lock.packages, location, dep))
+            .filter(|id| lockfile.packages.contains_key(id))
+            .collect();
+        lockfile.importers.insert(location.clone(), deps);
+    }
+
+    Ok(lockfile)
+}
+
+/// Keeps the root and workspace entries, links to kept workspaces and the
+/// packages in `keep`. The legacy `dependencies` tree isn't pruned but
+/// dropped, which makes the result a v3 lockfile.
+pub fn prune(
+    contents: &str,
+    keep: &BTreeSet<String>,
+    ws_paths: &[String],
+) -> Result<String, String> {
+    let mut lock: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let lock_object = lock
+        .as_object_mut()
+        .ok_or_else(|| "expected an object".to_owned())?;
+
+    if let Some(Value::Object(packages)) = lock_object.get_mut("packages") {
+        // Rebuilt rather than removed from, which would reorder entries.
+        *packages = std::mem::take(packages)
+            .into_iter()
+            .filter(|(location, entry)| keep_entry(location, entry, keep, ws_paths))
+            .collect();
+    }
+    if let Some(Value::Object(_)) = lock_object.get("dependencies") {
+        *lock_object = std::mem::take(lock_object)
+            .into_iter()
+            .filter(|(key, _)| key != "dependencies")
+            .collect();
+    }
+    lock_object.insert("lockfileVersion".to_owned(), Value::from(3));
+
+    let mut pruned = serde_json::to_string_pretty(&lock).map_err(|e| e.to_string())?;
+    pruned.push('\n');
+    Ok(pruned)
+}
+
+fn keep_entry(location: &str, entry: &Value, keep: &BTreeSet<String>, ws_paths: &[String]) -> bool {
+    if !location.contains("node_modules/") {
+        return ws_paths.iter().any(|p| p == location);
+    }
+    if entry.get("link").and_then(|l| l.as_bool()) == Some(true) {
+        return entry
+            .get("resolved")
+            .and_then(|r| r.as_str())
+            .is_some_and(|resolved| ws_paths.iter().any(|p| p == resolved));
+    }
+    keep.contains(location)
+}
+
+fn resolve(packages: &HashMap<String, Entry>, from: &str, dep: &str) -> Option<String> {
+    let mut location = from.to_owned();
+    loop {
+        let candidate = if location.is_empty() {
+            format!("node_modules/{}", dep)
+        } else {
+            format!("{}/node_modules/{}", location, dep)
+        };
+        if packages.contains_key(&candidate) {
+            return Some(candidate);
+        }
+        if location.is_empty() {
+            return None;
+        }
+        location = match location.rfind("/node_modules/") {
+            Some(idx) => location[..idx].to_owned(),
+            None => String::new(),
+        };
+    }
+}
+
+fn package_name(location: &str) -> &str {
+    let idx = location.rfind("node_modules/").unwrap_or(0);
+    &location[idx + "node_modules/".len()..]
+}
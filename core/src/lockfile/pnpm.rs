@@ -0,0 +1,277 @@
+use super::{LockPackage, Lockfile};
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// `/name/1.0.0`
+    V5,
+    /// `/name@1.0.0`
+    V6,
+    /// `name@1.0.0`, dependencies live in `snapshots`
+    V9,
+}
+
+/// pnpm-lock.yaml, lockfile versions 5.x, 6.x and 9.x.
+pub fn parse(contents: &str) -> Result<Lockfile, String> {
+    let doc: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let format = detect_format(&doc)?;
+
+    let mut lockfile = Lockfile::default();
+    let packages = doc.get("packages").and_then(|p| p.as_mapping());
+    let snapshots = doc.get("snapshots").and_then(|p| p.as_mapping());
+    let graph = if format == Format::V9 {
+        snapshots
+    } else {
+        packages
+    };
+
+    if let Some(graph) = graph {
+        for (key, entry) in graph {
+            let id = match key.as_str() {
+                Some(id) => id.to_owned(),
+                None => continue,
+            };
+            let (name, version) = split_key(&id, format);
+            let (name, version) = (name.to_owned(), version.to_owned());
+            let checksum = packages
+                .and_then(|p| p.get(&Value::String(strip_peers(&id, format).to_owned())))
+                .or(Some(entry))
+                .and_then(|p| p.get("resolution"))
+                .and_then(|r| r.get("integrity").or_else(|| r.get("tarball")))
+                .and_then(|i| i.as_str())
+                .unwrap_or("")
+                .to_owned();
+
+            lockfile.packages.insert(
+                id,
+                LockPackage {
+                    name,
+                    version,
+                    checksum,
+                    dependencies: dependency_ids(entry, format, false),
+                },
+            );
+        }
+    }
+
+    if let Some(importers) = doc.get("importers").and_then(|i| i.as_mapping()) {
+        for (path, importer) in importers {
+            let path = match path.as_str() {
+                Some(".") => "",
+                Some(path) => path,
+                None => continue,
+            };
+            lockfile
+                .importers
+                .insert(path.to_owned(), dependency_ids(importer, format, true));
+        }
+    } else {
+        lockfile
+            .importers
+            .insert(String::new(), dependency_ids(&doc, format, true));
+    }
+
+    Ok(lockfile)
+}
+
+/// Keeps the importers of kept workspaces and the packages in `keep`.
+pub fn prune(
+    contents: &str,
+    keep: &BTreeSet<String>,
+    ws_paths: &[String],
+) -> Result<String, String> {
+    let mut doc: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let format = detect_format(&doc)?;
+    // v9 `packages` is keyed by ids without the peers suffix.
+    let keep_packages: BTreeSet<&str> = keep.iter().map(|id| strip_peers(id, format)).collect();
+
+    let mapping = doc
+        .as_mapping_mut()
+        .ok_or_else(|| "expected a mapping".to_owned())?;
+    for field in &["importers", "packages", "snapshots"] {
+        let entries = match mapping
+            .get_mut(&Value::String((*field).to_owned()))
+            .and_then(|v| v.as_mapping_mut())
+        {
+            Some(entries) => entries,
+            None => continue,
+        };
+        // Rebuilt rather than removed from, which would reorder entries.
+        *entries = std::mem::take(entries)
+            .into_iter()
+            .filter(|(key, _)| {
+                let key = key.as_str().unwrap_or_default();
+                match *field {
+                    "importers" => ws_paths
+                        .iter()
+                        .any(|p| p == key || (p.is_empty() && key == ".")),
+                    "packages" if format == Format::V9 => keep_packages.contains(key),
+                    _ => keep.contains(key),
+                }
+            })
+            .collect();
+    }
+
+    let pruned = serde_yaml::to_string(&doc).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}\n",
+        pruned.trim_start_matches("---\n").trim_end()
+    ))
+}
+
+fn detect_format(doc: &Value) -> Result<Format, String> {
+    let version = match doc.get("lockfileVersion") {
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(0.0),
+        Some(Value::String(s)) => s.parse::<f64>().unwrap_or(0.0),
+        _ => return Err("missing lockfileVersion".to_owned()),
+    };
+    Ok(if version >= 9.0 {
+        Format::V9
+    } else if version >= 6.0 {
+        Format::V6
+    } else {
+        Format::V5
+    })
+}
+
+fn dependency_ids(entry: &Value, format: Format, include_dev: bool) -> Vec<String> {
+    let mut fields = vec!["dependencies", "optionalDependencies"];
+    if include_dev {
+        fields.push("devDependencies");
+    }
+
+    fields
+        .iter()
+        .filter_map(|field| entry.get(*field).and_then(|v| v.as_mapping()))
+        .flat_map(|deps| deps.iter())
+        .filter_map(|(name, version)| {
+            let version = match version {
+                Value::String(v) => v.as_str(),
+                // v6+ importers: { specifier, version }
+                Value::Mapping(_) => version.get("version")?.as_str()?,
+                _ => return None,
+            };
+            if version.starts_with("link:") || version.starts_with("file:") {
+                return None;
+            }
+            Some(package_id(name.as_str()?, version, format))
+        })
+        .collect()
+}
+
+fn package_id(name: &str, version: &str, format: Format) -> String {
+    match format {
+        // Aliased dependencies reference the full id instead of a version.
+        // Peers like `17.0.2(react@17.0.2)` have an `@` of their own.
+        Format::V5 | Format::V6 if version.starts_with('/') => version.to_owned(),
+        Format::V9
+            if strip_peers(version, format)
+                .get(1..)
+                .is_some_and(|v| v.contains('@')) =>
+        {
+            version.to_owned()
+        }
+        Format::V5 => format!("/{}/{}", name, version),
+        Format::V6 => format!("/{}@{}", name, version),
+        Format::V9 => format!("{}@{}", name, version),
+    }
+}
+
+fn split_key(key: &str, format: Format) -> (&str, &str) {
+    let key = key.trim_start_matches('/');
+    let separator = match format {
+        Format::V5 => key.rfind('/'),
+        Format::V6 | Format::V9 => key.get(1..).and_then(|k| k.find('@')).map(|idx| idx + 1),
+    };
+    match separator {
+        Some(idx) => (&key[..idx], &key[idx + 1..]),
+        None => (key, ""),
+    }
+}
+
+/// Drops the peer dependency suffix, `(react@17.0.0)` in v6+ and
+/// `_react@17.0.0` in v5.
+fn strip_peers(key: &str, format: Format) -> &str {
+    let idx = match format {
+        Format::V5 => key.find('_'),
+        Format::V6 | Format::V9 => key.find('('),
+    };
+    match idx {
+        Some(idx) => &key[..idx],
+        None => key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V9: &str = r#"lockfileVersion: '9.0'
+importers:
+  .:
+    dependencies:
+      react:
+        specifier: 17.0.2
+        version: 17.0.2
+      react-dom:
+        specifier: 17.0.2
+        version: 17.0.2(react@17.0.2)
+      string-width-cjs:
+        specifier: npm:string-width@4.2.3
+        version: string-width@4.2.3
+packages:
+  react@17.0.2:
+    resolution: {integrity: sha512-react}
+  react-dom@17.0.2:
+    resolution: {integrity: sha512-react-dom}
+  string-width@4.2.3:
+    resolution: {integrity: sha512-string-width}
+snapshots:
+  react@17.0.2: {}
+  react-dom@17.0.2(react@17.0.2):
+    dependencies:
+      react: 17.0.2
+  string-width@4.2.3: {}
+"#;
+
+    #[test]
+    fn peer_suffixed_v9_versions_are_not_aliases() {
+        let lockfile = parse(V9).unwrap();
+        let mut root = lockfile.importers[""].clone();
+        root.sort();
+        assert_eq!(
+            root,
+            vec![
+                "react-dom@17.0.2(react@17.0.2)",
+                "react@17.0.2",
+                "string-width@4.2.3",
+            ]
+        );
+
+        let react_dom = &lockfile.packages["react-dom@17.0.2(react@17.0.2)"];
+        assert_eq!(react_dom.name, "react-dom");
+        assert_eq!(react_dom.checksum, "sha512-react-dom");
+        assert_eq!(react_dom.dependencies, vec!["react@17.0.2"]);
+    }
+
+    #[test]
+    fn package_ids() {
+        assert_eq!(
+            package_id("react-dom", "17.0.2(react@17.0.2)", Format::V9),
+            "react-dom@17.0.2(react@17.0.2)"
+        );
+        assert_eq!(
+            package_id("string-width-cjs", "string-width@4.2.3", Format::V9),
+            "string-width@4.2.3"
+        );
+        assert_eq!(
+            package_id("@types/node", "@types/node@20.0.0", Format::V9),
+            "@types/node@20.0.0"
+        );
+        assert_eq!(
+            package_id("react-dom", "17.0.2(react@17.0.2)", Format::V6),
+            "/react-dom@17.0.2(react@17.0.2)"
+        );
+    }
+}
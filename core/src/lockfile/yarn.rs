@@ -0,0 +1,286 @@
+use super::{split_descriptor, LockPackage, Lockfile};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// yarn.lock, both the classic (v1) custom format and the berry (v2+) YAML
+/// one. Entries are keyed by the descriptors (`name@range`) that resolve to
+/// them.
+pub fn parse(contents: &str) -> Result<Lockfile, String> {
+    if contents.contains("__metadata:") {
+        parse_berry(contents)
+    } else {
+        parse_classic(contents)
+    }
+}
+
+/// Keeps the entries resolving to packages in `keep` and, for berry, the
+/// kept workspaces.
+pub fn prune(
+    contents: &str,
+    keep: &BTreeSet<String>,
+    ws_paths: &[String],
+) -> Result<String, String> {
+    if contents.contains("__metadata:") {
+        prune_berry(contents, keep, ws_paths)
+    } else {
+        Ok(prune_classic(contents, keep))
+    }
+}
+
+fn parse_classic(contents: &str) -> Result<Lockfile, String> {
+    let mut lockfile = Lockfile::default();
+    // Dependencies are descriptors until every entry has been read.
+    let mut pending: Vec<(String, Vec<String>)> = vec![];
+
+    let mut current: Option<(Vec<String>, LockPackage)> = None;
+    let mut in_deps = false;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+
+        if indent == 0 {
+            if let Some((descriptors, package)) = current.take() {
+                add_classic_entry(&mut lockfile, &mut pending, descriptors, package);
+            }
+            let descriptors = line
+                .trim_end_matches(':')
+                .split(", ")
+                .map(|d| unquote(d).to_owned())
+                .collect::<Vec<String>>();
+            let name = descriptors
+                .first()
+                .and_then(|d| split_descriptor(d))
+                .map(|(name, _)| name.to_owned())
+                .ok_or_else(|| format!("invalid entry \"{}\"", line))?;
+            current = Some((
+                descriptors,
+                LockPackage {
+                    name,
+                    ..LockPackage::default()
+                },
+            ));
+            in_deps = false;
+            continue;
+        }
+
+        let (_, package) = match current.as_mut() {
+            Some(current) => current,
+            None => return Err(format!("unexpected line \"{}\"", line)),
+        };
+
+        if indent == 2 {
+            in_deps = line == "dependencies:" || line == "optionalDependencies:";
+            let (key, value) = split_pair(line);
+            match key {
+                "version" => package.version = value.to_owned(),
+                "integrity" => package.checksum = value.to_owned(),
+                "resolved" if package.checksum.is_empty() => package.checksum = value.to_owned(),
+                _ => {}
+            }
+        } else if in_deps {
+            let (name, range) = split_pair(line);
+            package.dependencies.push(format!("{}@{}", name, range));
+        }
+    }
+
+    if let Some((descriptors, package)) = current.take() {
+        add_classic_entry(&mut lockfile, &mut pending, descriptors, package);
+    }
+
+    resolve_pending(&mut lockfile, pending);
+    Ok(lockfile)
+}
+
+fn add_classic_entry(
+    lockfile: &mut Lockfile,
+    pending: &mut Vec<(String, Vec<String>)>,
+    descriptors: Vec<String>,
+    package: LockPackage,
+) {
+    let id = format!("{}@{}", package.name, package.version);
+    for descriptor in descriptors {
+        lockfile.descriptors.insert(descriptor, id.clone());
+    }
+    pending.push((id.clone(), package.dependencies.clone()));
+    lockfile.packages.insert(id, package);
+}
+
+fn parse_berry(contents: &str) -> Result<Lockfile, String> {
+    let doc: HashMap<String, Value> = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let mut lockfile = Lockfile::default();
+    let mut pending: Vec<(String, Vec<String>)> = vec![];
+    let mut workspaces: Vec<(String, Vec<String>)> = vec![];
+
+    for (key, entry) in &doc {
+        if key == "__metadata" {
+            continue;
+        }
+
+        let resolution = str_field(entry, "resolution");
+        let dependencies: Vec<String> = ["dependencies", "optionalDependencies"]
+            .iter()
+            .filter_map(|field| entry.get(*field).and_then(|v| v.as_mapping()))
+            .flat_map(|deps| deps.iter())
+            .filter_map(|(name, range)| Some(format!("{}@{}", name.as_str()?, range.as_str()?)))
+            .collect();
+
+        for descriptor in key.split(", ") {
+            lockfile
+                .descriptors
+                .insert(descriptor.trim().to_owned(), resolution.clone());
+        }
+
+        if let Some(idx) = resolution.find("@workspace:") {
+            let path = &resolution[idx + "@workspace:".len()..];
+            let path = if path == "." { "" } else { path };
+            workspaces.push((path.to_owned(), dependencies));
+            continue;
+        }
+
+        let name = split_descriptor(&resolution)
+            .map(|(name, _)| name.to_owned())
+            .unwrap_or_default();
+        let checksum = match str_field(entry, "checksum") {
+            checksum if checksum.is_empty() => resolution.clone(),
+            checksum => checksum,
+        };
+        pending.push((resolution.clone(), dependencies));
+        lockfile.packages.insert(
+            resolution.clone(),
+            LockPackage {
+                name,
+                version: str_field(entry, "version"),
+                checksum,
+                dependencies: vec![],
+            },
+        );
+    }
+
+    for (path, dependencies) in workspaces {
+        let ids = dependencies
+            .iter()
+            .filter_map(|d| lockfile.descriptors.get(d))
+            .filter(|id| lockfile.packages.contains_key(*id))
+            .cloned()
+            .collect();
+        lockfile.importers.insert(path, ids);
+    }
+
+    resolve_pending(&mut lockfile, pending);
+    Ok(lockfile)
+}
+
+/// Works on the text rather than re-serializing, so the kept entries stay
+/// byte for byte the same.
+fn prune_classic(contents: &str, keep: &BTreeSet<String>) -> String {
+    let mut header = String::new();
+    let mut entries: Vec<String> = vec![];
+
+    for line in contents.lines() {
+        let is_entry_start = !line.is_empty() && !line.starts_with(' ') && !line.starts_with('#');
+        match entries.last_mut() {
+            Some(entry) if !is_entry_start => {
+                entry.push_str(line);
+                entry.push('\n');
+            }
+            None if !is_entry_start => {
+                header.push_str(line);
+                header.push('\n');
+            }
+            _ => entries.push(format!("{}\n", line)),
+        }
+    }
+
+    let kept: Vec<&str> = entries
+        .iter()
+        .filter(|entry| classic_entry_id(entry).is_some_and(|id| keep.contains(&id)))
+        .map(|entry| entry.trim_end())
+        .collect();
+    format!("{}{}\n", header, kept.join("\n\n"))
+}
+
+fn classic_entry_id(entry: &str) -> Option<String> {
+    let mut lines = entry.lines();
+    let descriptor = lines.next()?.split(", ").next()?.trim_end_matches(':');
+    let (name, _) = split_descriptor(unquote(descriptor))?;
+    let version = lines
+        .map(|line| split_pair(line.trim()))
+        .find(|(key, _)| *key == "version")?
+        .1;
+    Some(format!("{}@{}", name, version))
+}
+
+fn prune_berry(
+    contents: &str,
+    keep: &BTreeSet<String>,
+    ws_paths: &[String],
+) -> Result<String, String> {
+    let doc: Mapping = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let pruned: Mapping = doc
+        .into_iter()
+        .filter(|(key, entry)| {
+            if key.as_str() == Some("__metadata") {
+                return true;
+            }
+            let resolution = str_field(entry, "resolution");
+            match resolution.find("@workspace:") {
+                Some(idx) => {
+                    let path = &resolution[idx + "@workspace:".len()..];
+                    let path = if path == "." { "" } else { path };
+                    ws_paths.iter().any(|p| p == path)
+                }
+                None => keep.contains(&resolution),
+            }
+        })
+        .collect();
+
+    // The header comment is what yarn uses to tell its lockfile apart.
+    let header: String = contents
+        .lines()
+        .take_while(|line| line.starts_with('#') || line.is_empty())
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let body = serde_yaml::to_string(&pruned).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}{}\n",
+        header,
+        body.trim_start_matches("---\n").trim_end()
+    ))
+}
+
+fn resolve_pending(lockfile: &mut Lockfile, pending: Vec<(String, Vec<String>)>) {
+    for (id, descriptors) in pending {
+        let deps: Vec<String> = descriptors
+            .iter()
+            .filter_map(|d| lockfile.descriptors.get(d).cloned())
+            .collect();
+        if let Some(package) = lockfile.packages.get_mut(&id) {
+            package.dependencies = deps;
+        }
+    }
+}
+
+fn str_field(entry: &Value, field: &str) -> String {
+    match entry.get(field) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn split_pair(line: &str) -> (&str, &str) {
+    let line = line.trim_end_matches(':');
+    match line.find(' ') {
+        Some(idx) => (unquote(&line[..idx]), unquote(line[idx..].trim())),
+        None => (unquote(line), ""),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
@@ -0,0 +1,285 @@
+//! The parts of `package.json` the tool reads.
+
+use super::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackageJson {
+    pub path: String,
+    pub name: String,
+    pub version: String,
+    pub workspaces_config: Vec<String>,
+    pub dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    pub dev_dependencies: HashMap<String, String>,
+
+    /// Never published.
+    #[serde(default)]
+    pub private: bool,
+
+    #[serde(default)]
+    pub scripts: HashMap<String, String>,
+
+    #[serde(default)]
+    pub config: Config,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageJsonData {
+    name: String,
+
+    #[serde(default = "default_version")]
+    version: String,
+
+    #[serde(default = "default_workspaces")]
+    workspaces: Vec<String>,
+
+    #[serde(default = "default_deps")]
+    dependencies: HashMap<String, String>,
+
+    #[serde(default = "default_deps", rename = "devDependencies")]
+    dev_dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    private: bool,
+
+    #[serde(default)]
+    scripts: HashMap<String, String>,
+
+    #[serde(default)]
+    sculpture: Config,
+}
+
+impl PackageJson {
+    pub fn new(path: &str) -> PackageJson {
+        let pkg_json_data = read_pkg_json(path);
+        PackageJson {
+            path: path.to_owned(),
+            name: pkg_json_data.name,
+            version: pkg_json_data.version,
+            workspaces_config: pkg_json_data.workspaces,
+            dependencies: pkg_json_data.dependencies,
+            dev_dependencies: pkg_json_data.dev_dependencies,
+            private: pkg_json_data.private,
+            scripts: pkg_json_data.scripts,
+            config: pkg_json_data.sculpture,
+        }
+    }
+
+    pub fn get_workspaces_config(&self) -> &Vec<String> {
+        &self.workspaces_config
+    }
+
+    /// Replaces the spec of a single dependency in place, leaving the rest of
+    /// the file's formatting untouched.
+    pub fn set_dependency_spec(
+        &self,
+        dep: &str,
+        old_spec: &str,
+        new_spec: &str,
+    ) -> Result<(), String> {
+        let mut edit = self.edit()?;
+        edit.set_dependency_spec(dep, old_spec, new_spec)?;
+        edit.write()
+    }
+
+    /// Starts editing the file as it is on disk.
+    pub fn edit(&self) -> Result<ManifestEdit, String> {
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Couldn't read {}: {}", self.path, e))?;
+        Ok(ManifestEdit {
+            path: self.path.clone(),
+            contents,
+        })
+    }
+}
+
+/// Changes to the text of a `package.json`, which keep the rest of its
+/// formatting. Nothing is written before `write`, so several files can be
+/// edited and only written once all edits succeeded.
+pub struct ManifestEdit {
+    path: String,
+    contents: String,
+}
+
+impl ManifestEdit {
+    pub fn set_dependency_spec(
+        &mut self,
+        dep: &str,
+        old_spec: &str,
+        new_spec: &str,
+    ) -> Result<(), String> {
+        self.contents = replace_dependency_spec(&self.contents, dep, old_spec, new_spec)
+            .ok_or_else(|| {
+                format!(
+                    "Couldn't find \"{}\": \"{}\" in dependencies of {}",
+                    dep, old_spec, self.path
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Replaces the top-level `version`.
+    pub fn set_version(&mut self, old_version: &str, new_version: &str) -> Result<(), String> {
+        self.contents =
+            replace_version(&self.contents, old_version, new_version).ok_or_else(|| {
+                format!(
+                    "Couldn't find \"version\": \"{}\" in {}",
+                    old_version, self.path
+                )
+            })?;
+        Ok(())
+    }
+
+    pub fn write(&self) -> Result<(), String> {
+        fs::write(&self.path, &self.contents)
+            .map_err(|e| format!("Couldn't write {}: {}", self.path, e))
+    }
+}
+
+/// Only a `"version"` key of the root object, nested ones are left alone.
+fn replace_version(contents: &str, old_version: &str, new_version: &str) -> Option<String> {
+    let version = value_range(contents, "version")?;
+    replace_string(contents, version, old_version, new_version)
+}
+
+/// Only in the top-level `"dependencies"`, not in objects of the same name
+/// nested elsewhere, e.g. in `"peerDependenciesMeta"`.
+fn replace_dependency_spec(
+    contents: &str,
+    dep: &str,
+    old_spec: &str,
+    new_spec: &str,
+) -> Option<String> {
+    let section = value_range(contents, "dependencies")?;
+    let spec = value_range(&contents[section.clone()], dep)?;
+    replace_string(
+        contents,
+        section.start + spec.start..section.start + spec.end,
+        old_spec,
+        new_spec,
+    )
+}
+
+/// Replaces the JSON string at `range` if it's `old`.
+fn replace_string(contents: &str, range: Range<usize>, old: &str, new: &str) -> Option<String> {
+    if contents[range.clone()] != format!("\"{}\"", old) {
+        return None;
+    }
+    Some(format!(
+        "{}\"{}\"{}",
+        &contents[..range.start],
+        new,
+        &contents[range.end..]
+    ))
+}
+
+/// Byte range of the value of `key` in the object `json` starts with. Keys
+/// of nested objects and strings that happen to match are skipped.
+fn value_range(json: &str, key: &str) -> Option<Range<usize>> {
+    let bytes = json.as_bytes();
+    let quoted = format!("\"{}\"", key);
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let end = i + string_len(&bytes[i..])?;
+                let rest = json[end..].trim_start();
+                if depth == 1 && rest.starts_with(':') && json[i..end] == quoted {
+                    let value = rest[1..].trim_start();
+                    let start = json.len() - value.len();
+                    return Some(start..start + value_len(value.as_bytes())?);
+                }
+                i = end;
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth <= 0 {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Length of the JSON value `json` starts with.
+fn value_len(json: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < json.len() {
+        match json[i] {
+            b'"' => {
+                i += string_len(&json[i..])?;
+                if depth == 0 {
+                    return Some(i);
+                }
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            // The end of a number, boolean or null.
+            b',' | b'}' | b']' if depth == 0 => return Some(i),
+            c if depth == 0 && c.is_ascii_whitespace() => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if depth == 0 {
+        Some(i)
+    } else {
+        None
+    }
+}
+
+/// Length of the JSON string `json` starts with, quotes included.
+fn string_len(json: &[u8]) -> Option<usize> {
+    let mut escaped = false;
+    for (i, &c) in json.iter().enumerate().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn read_pkg_json(path: &str) -> PackageJsonData {
+    let contents = fs::read_to_string(path).expect("Something went wrong reading the file");
+    let data: PackageJsonData =
+        serde_json::from_str(&contents).expect("Cannot read package.json file");
+
+    data
+}
+
+fn default_workspaces() -> Vec<String> {
+    vec![]
+}
+
+fn default_deps() -> HashMap<String, String> {
+    HashMap::new()
+}
+
+fn default_version() -> String {
+    "0.0.0".to_string()
+}
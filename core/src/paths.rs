@@ -0,0 +1,21 @@
+//! Path helpers shared by the spec parser and the resolvers.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves `.` and `..` without touching the file system. A leading `..`
+/// is kept, the way `tsconfig` paths outside the base directory are.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            _ => result.push(component),
+        }
+    }
+    result
+}
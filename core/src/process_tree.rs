@@ -0,0 +1,183 @@
+//! Stopping task processes along with everything they started.
+
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Time processes get to exit after SIGTERM before they're killed.
+pub const TERMINATE_GRACE: Duration = Duration::from_secs(5);
+
+/// Signal number of SIGINT, what `interrupt` stops tasks as.
+#[cfg(unix)]
+const SIGINT: i32 = libc::SIGINT;
+#[cfg(not(unix))]
+const SIGINT: i32 = 2;
+
+/// The signal tasks were interrupted by, 0 when they weren't.
+static INTERRUPTED: AtomicI32 = AtomicI32::new(0);
+
+/// Puts the process in a process group of its own, which its children
+/// join unless they start one too. The group can be signalled at once and
+/// doesn't receive the terminal's Ctrl-C, so stopping it is up to us.
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// How often terminated process groups are checked for leftovers.
+const POLL: Duration = Duration::from_millis(50);
+
+/// A process group that was sent SIGTERM, killed if anything is left of it
+/// after [`TERMINATE_GRACE`].
+pub struct Terminating {
+    #[cfg(unix)]
+    group: libc::pid_t,
+    since: Instant,
+}
+
+/// Sends SIGTERM to the process group of the child. Elsewhere, the child is
+/// killed and nothing else is left to wait for.
+pub fn terminate(child: &mut Child) -> Terminating {
+    #[cfg(unix)]
+    {
+        let group = -(child.id() as libc::pid_t);
+        // Safety: kill only sends a signal.
+        unsafe { libc::kill(group, libc::SIGTERM) };
+        Terminating {
+            group,
+            since: Instant::now(),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child.kill();
+        Terminating {
+            since: Instant::now(),
+        }
+    }
+}
+
+impl Terminating {
+    /// Kills what's left of the group once the grace period is over.
+    /// Returns whether there's nothing left to wait for.
+    pub fn poll(&self) -> bool {
+        if !self.is_alive() {
+            return true;
+        }
+        if self.since.elapsed() < TERMINATE_GRACE {
+            return false;
+        }
+        #[cfg(unix)]
+        // Safety: kill only sends a signal.
+        unsafe {
+            libc::kill(self.group, libc::SIGKILL);
+        }
+        true
+    }
+
+    /// Blocks until the group exited or is killed at the end of the grace
+    /// period.
+    pub fn wait(&self) {
+        while !self.poll() {
+            std::thread::sleep(POLL);
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        // The group's id isn't reused while any of its processes is alive,
+        // so this only finds processes of the task.
+        #[cfg(unix)]
+        // Safety: signal 0 only checks whether the group exists.
+        unsafe {
+            libc::kill(self.group, 0) == 0
+        }
+        #[cfg(not(unix))]
+        false
+    }
+}
+
+/// Catches SIGINT and SIGTERM while alive, so running tasks can be
+/// terminated before we exit. Restores the previous handlers when dropped.
+/// Meant for processes of our own, the handlers replace those of a host
+/// like Node.
+pub struct Interrupts {
+    #[cfg(unix)]
+    previous: Vec<(libc::c_int, libc::sigaction)>,
+}
+
+impl Interrupts {
+    pub fn catch() -> Self {
+        #[cfg(unix)]
+        {
+            let previous = [libc::SIGINT, libc::SIGTERM]
+                .iter()
+                .map(|&signal| {
+                    // Safety: sigaction structs are plain data, the handler
+                    // only stores to an atomic.
+                    unsafe {
+                        let mut action: libc::sigaction = std::mem::zeroed();
+                        action.sa_sigaction = on_signal as *const () as libc::sighandler_t;
+                        libc::sigemptyset(&mut action.sa_mask);
+                        let mut previous: libc::sigaction = std::mem::zeroed();
+                        libc::sigaction(signal, &action, &mut previous);
+                        (signal, previous)
+                    }
+                })
+                .collect();
+            Self { previous }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+}
+
+impl Drop for Interrupts {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        for (signal, previous) in &self.previous {
+            // Safety: restores what sigaction returned.
+            unsafe { libc::sigaction(*signal, previous, std::ptr::null_mut()) };
+        }
+    }
+}
+
+/// Stops the running tasks as if SIGINT was caught. For hosts that handle
+/// signals themselves, like Node.
+pub fn interrupt() {
+    INTERRUPTED.store(SIGINT, Ordering::SeqCst);
+}
+
+/// The signal tasks were interrupted by since the last `reset`, if any.
+pub fn interrupted() -> Option<i32> {
+    match INTERRUPTED.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Forgets an interrupt, so it doesn't carry over to the next run.
+pub fn reset() {
+    INTERRUPTED.store(0, Ordering::SeqCst);
+}
+
+/// Sends the signal to ourselves once its handler is restored, so the
+/// process ends the way it would have without us catching it.
+pub fn reraise(signal: i32) {
+    #[cfg(unix)]
+    // Safety: raise only sends a signal.
+    unsafe {
+        libc::raise(signal);
+    }
+    #[cfg(not(unix))]
+    let _ = signal;
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    INTERRUPTED.store(signal, Ordering::SeqCst);
+}
@@ -0,0 +1,179 @@
+//! Chrome Trace Event profiles of a run.
+
+use serde::Serialize;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+static NEXT_LANE: AtomicU64 = AtomicU64::new(1);
+static NEXT_ASYNC_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static LANE: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Collects Chrome Trace Event Format events, which can be loaded into
+/// Perfetto or chrome://tracing. A disabled profiler records nothing.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    start: Instant,
+    events: Option<Arc<Mutex<Vec<TraceEvent>>>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TraceEvent {
+    name: String,
+    cat: String,
+    ph: &'static str,
+    ts: u128,
+    pid: u32,
+    tid: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<u128>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct TraceFile<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: &'a [TraceEvent],
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'static str,
+}
+
+/// Records a complete ("X") event on the current thread's lane when dropped.
+pub struct Span {
+    profiler: Profiler,
+    name: String,
+    cat: &'static str,
+    start: u128,
+}
+
+/// Records a pair of async ("b"/"e") events. Used for work that interleaves
+/// on a single thread, e.g. futures polled by the same executor.
+pub struct AsyncSpan {
+    profiler: Profiler,
+    name: String,
+    cat: &'static str,
+    id: u64,
+}
+
+impl Profiler {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            start: Instant::now(),
+            events: if enabled {
+                Some(Arc::new(Mutex::new(vec![])))
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn span(&self, name: &str, cat: &'static str) -> Span {
+        Span {
+            profiler: self.clone(),
+            name: name.to_owned(),
+            cat,
+            start: self.now(),
+        }
+    }
+
+    pub fn async_span(&self, name: &str, cat: &'static str) -> AsyncSpan {
+        let id = NEXT_ASYNC_ID.fetch_add(1, Ordering::Relaxed);
+        self.record(name, cat, "b", self.now(), None, Some(id), None);
+        AsyncSpan {
+            profiler: self.clone(),
+            name: name.to_owned(),
+            cat,
+            id,
+        }
+    }
+
+    /// Names the current thread's lane, e.g. "runner" or "worker 3".
+    pub fn name_lane(&self, name: &str) {
+        self.record(
+            "thread_name",
+            "__metadata",
+            "M",
+            0,
+            None,
+            None,
+            Some(serde_json::json!({ "name": name })),
+        );
+    }
+
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        let events = match &self.events {
+            Some(events) => events.lock().unwrap(),
+            None => return Ok(()),
+        };
+        let trace = TraceFile {
+            trace_events: &events,
+            display_time_unit: "ms",
+        };
+        std::fs::write(path, serde_json::to_string(&trace).unwrap())
+    }
+
+    fn now(&self) -> u128 {
+        self.start.elapsed().as_micros()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record(
+        &self,
+        name: &str,
+        cat: &str,
+        ph: &'static str,
+        ts: u128,
+        dur: Option<u128>,
+        id: Option<u64>,
+        args: Option<serde_json::Value>,
+    ) {
+        if let Some(events) = &self.events {
+            events.lock().unwrap().push(TraceEvent {
+                name: name.to_owned(),
+                cat: cat.to_owned(),
+                ph,
+                ts,
+                pid: 1,
+                tid: current_lane(),
+                dur,
+                id,
+                args,
+            });
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let dur = self.profiler.now() - self.start;
+        self.profiler
+            .record(&self.name, self.cat, "X", self.start, Some(dur), None, None);
+    }
+}
+
+impl Drop for AsyncSpan {
+    fn drop(&mut self) {
+        let ts = self.profiler.now();
+        self.profiler
+            .record(&self.name, self.cat, "e", ts, None, Some(self.id), None);
+    }
+}
+
+fn current_lane() -> u64 {
+    LANE.with(|lane| {
+        if lane.get() == 0 {
+            lane.set(NEXT_LANE.fetch_add(1, Ordering::Relaxed));
+        }
+        lane.get()
+    })
+}
@@ -0,0 +1,271 @@
+//! A repository root and the workspaces it declares.
+
+use super::config::Config;
+use super::file_cache::FileCache;
+use super::git_index::GitIndex;
+use super::hasher::Hasher;
+use super::lockfile::Lockfile;
+use super::package_json::PackageJson;
+use super::profiler::Profiler;
+use super::resolvers::{ResolverRegistry, Selection, SharedResolver};
+use super::workspace::Workspace;
+use async_std::task;
+use futures::future::join_all;
+use futures::stream::{FuturesUnordered, StreamExt};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+
+/// A repository root with a `workspaces` field in its `package.json`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Project {
+    pub path: String,
+    pkg_json: PackageJson,
+    /// By name.
+    pub workspaces: HashMap<String, Workspace>,
+}
+
+impl Project {
+    /// Discovers the workspaces of the project at `path`, without reading
+    /// any of their files. Panics on duplicate workspace names.
+    pub fn new(path: String) -> Self {
+        let pkg_json = PackageJson::new(&format!("{}/package.json", &path));
+        let workspaces_list = get_workspaces(&path, pkg_json.get_workspaces_config());
+        let mut workspaces = HashMap::new();
+        for ws in workspaces_list {
+            if workspaces.contains_key(&ws.name) {
+                panic!("Duplicate workspace with name – {}", ws.name);
+            }
+
+            workspaces.insert(ws.name.to_owned(), ws);
+        }
+
+        Self {
+            path,
+            pkg_json,
+            workspaces,
+        }
+    }
+
+    /// The project as of the last run, or a newly discovered one.
+    pub fn create_or_cached(cache: &FileCache, cwd: &str) -> Project {
+        if let Some(project) = Self::cached(cache) {
+            println!("Project restored from cache");
+            println!();
+            println!("--------------");
+            println!();
+            return project;
+        }
+
+        Project::new(cwd.to_owned())
+    }
+
+    /// The project as of the last run, if there was one.
+    pub fn cached(cache: &FileCache) -> Option<Project> {
+        if !cache.has("project.json") {
+            return None;
+        }
+
+        let serialized = cache.read("project.json").unwrap();
+        Some(serde_json::from_str(&serialized).unwrap())
+    }
+
+    /// The root `package.json`.
+    pub fn package_json(&self) -> &PackageJson {
+        &self.pkg_json
+    }
+
+    /// The `sculpture` config of the root `package.json`.
+    pub fn config(&self) -> &Config {
+        &self.pkg_json.config
+    }
+
+    /// Re-reads the root `package.json`, which is otherwise only read when
+    /// the project isn't restored from cache.
+    pub fn refresh_package_json(&mut self) {
+        self.pkg_json = PackageJson::new(&self.pkg_json.path);
+    }
+
+    /// Resolves and hashes the inputs of every workspace, comparing them and
+    /// the lockfile with the previous state. Returns the workspaces with their
+    /// new files and the changed files of the updated ones, by name.
+    pub fn invalidate(
+        &self,
+        resolvers: &ResolverRegistry,
+        profiler: &Profiler,
+    ) -> (Vec<Workspace>, HashMap<String, Vec<String>>) {
+        eprint!("Creating a list of workspaces... ");
+        let ws_start = Instant::now();
+        let discover_span = profiler.span("discover workspaces", "glob");
+        let workspaces_list = get_workspaces(&self.path, self.pkg_json.get_workspaces_config());
+        drop(discover_span);
+        eprintln!("[{} ms]", ws_start.elapsed().as_millis());
+
+        let lockfile_span = profiler.span("read lockfile", "lockfile");
+        let lockfile = match Lockfile::detect(&self.path) {
+            Some(Ok(lockfile)) => Some(lockfile),
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                None
+            }
+            None => None,
+        };
+        drop(lockfile_span);
+
+        // Only worth it when some workspace has nothing cached.
+        let has_uncached = workspaces_list.iter().any(|ws| {
+            self.workspaces
+                .get(&ws.name)
+                .is_none_or(|cached| cached.files.is_empty())
+        });
+        let git_index = if self.config().git_hashes && has_uncached {
+            let _span = profiler.span("read git index", "hash");
+            GitIndex::read(&self.path)
+                .map_err(|e| eprintln!("Couldn't seed hashes from git: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let git_index = git_index.as_ref();
+
+        let hasher = Hasher::new(self.config().hash_threads)
+            .expect("[project:invalidate] Couldn't start the hashing pool");
+        let hasher = &hasher;
+        let mut future_list = FuturesUnordered::new();
+        let invalidate_fut_list_start = Instant::now();
+        eprint!("Creating a list of workspace invalidate futures... ");
+        for cur_ws in workspaces_list {
+            let ws = if self.workspaces.contains_key(&cur_ws.name) {
+                self.workspaces.get(&cur_ws.name).unwrap().clone()
+            } else {
+                cur_ws.clone()
+            };
+
+            let selection = resolvers
+                .select(
+                    &ws.path,
+                    &ws.package_json.config.resolvers,
+                    &self.config().resolvers,
+                )
+                .unwrap_or_else(|e| {
+                    eprintln!(
+                        "{} in {}, using \"{}\"",
+                        e,
+                        ws.name,
+                        resolvers.fallback().name()
+                    );
+                    Selection {
+                        resolvers: vec![resolvers.fallback()],
+                        fallback: None,
+                    }
+                });
+            // Started right away, so JS plugins work while the next
+            // workspaces are resolved.
+            let resolving: Vec<_> = selection
+                .resolvers
+                .iter()
+                .map(|resolver| resolve_inputs(&ws.name, &ws.path, resolver, profiler))
+                .collect();
+            let profiler = profiler.clone();
+            let lockfile = lockfile.as_ref();
+            let root = &self.path;
+
+            let fut = async move {
+                let mut files: Vec<String> = join_all(resolving).await.concat();
+                match &selection.fallback {
+                    Some(fallback) if files.is_empty() => {
+                        files = resolve_inputs(&ws.name, &ws.path, fallback, &profiler).await;
+                    }
+                    _ => {}
+                }
+
+                let hash_span = profiler.async_span(&format!("hash: {}", ws.name), "hash");
+                let (mut changed_files, new_files) = ws.invalidate(files, git_index, hasher).await;
+                drop(hash_span);
+                // Kept even when nothing changed, the files carry the stat
+                // and hash of this run.
+                let mut ws = ws;
+                ws.files = new_files;
+                if !changed_files.is_empty() {
+                    ws.reload_package_json();
+                }
+
+                // Hashed once package.json is reloaded, the dependencies it
+                // lists select the lockfile entries without importers.
+                if let Some(lockfile) = lockfile {
+                    let hash = lockfile.workspace_hash(&ws.relative_path(root), &ws.package_json);
+                    if ws.lockfile_hash.as_ref() != Some(&hash) {
+                        changed_files.push(lockfile.path.clone());
+                        ws.lockfile_hash = Some(hash);
+                    }
+                }
+
+                (changed_files, ws)
+            };
+
+            future_list.push(fut);
+        }
+        eprintln!("[{} ms]", invalidate_fut_list_start.elapsed().as_millis());
+
+        let mut result_workspaces: Vec<Workspace> = vec![];
+        let mut updated_workspaces: HashMap<String, Vec<String>> = HashMap::new();
+
+        task::block_on(async {
+            while let Some((changed_files, ws)) = future_list.next().await {
+                if !changed_files.is_empty() {
+                    updated_workspaces.insert(ws.name.to_owned(), changed_files);
+                }
+                result_workspaces.push(ws);
+            }
+        });
+
+        (result_workspaces, updated_workspaces)
+    }
+}
+
+fn resolve_inputs(
+    ws_name: &str,
+    ws_path: &str,
+    resolver: &SharedResolver,
+    profiler: &Profiler,
+) -> impl Future<Output = Vec<String>> {
+    let span = profiler.async_span(
+        &format!("resolve inputs: {} ({})", ws_name, resolver.name()),
+        "inputs",
+    );
+    let resolving = resolver.resolve(ws_path);
+    let ws_name = ws_name.to_owned();
+    let resolver_name = resolver.name().to_owned();
+
+    async move {
+        let files = resolving.await.unwrap_or_else(|e| {
+            eprintln!(
+                "Couldn't resolve inputs of {} with \"{}\": {}",
+                ws_name, resolver_name, e
+            );
+            vec![]
+        });
+        drop(span);
+        files
+    }
+}
+
+fn get_workspaces(path: &str, workspaces_config: &[String]) -> Vec<Workspace> {
+    let mut result_workspaces: Vec<Workspace> = vec![];
+
+    for ws in workspaces_config {
+        for entry in
+            glob(&format!("{}/{}/package.json", path, ws)).expect("Failed to read glob pattern")
+        {
+            match entry {
+                Ok(p) => result_workspaces
+                    .push(Workspace::new(p.into_os_string().into_string().unwrap())),
+                Err(e) => eprintln!("{:?}", e),
+            }
+        }
+    }
+
+    result_workspaces
+}
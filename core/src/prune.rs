@@ -0,0 +1,179 @@
+//! Writing a minimal subset of the repository for a single workspace.
+
+use super::dependency_graph::DepGraph;
+use super::lockfile::Lockfile;
+use super::project::Project;
+use super::workspace::Workspace;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Root files the package managers read on install, copied as is.
+const ROOT_FILES: &[&str] = &[".npmrc", ".yarnrc", ".yarnrc.yml", "pnpm-workspace.yaml"];
+
+/// Directories never copied into the output.
+const SKIP_DIRS: &[&str] = &["node_modules", ".git", ".cache"];
+
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    pub workspace: String,
+    pub out_dir: String,
+    /// Split the output into `json/`, with only manifests and the lockfile,
+    /// and `full/`, with sources, so installing dependencies gets its own
+    /// Docker layer.
+    pub docker: bool,
+}
+
+/// Writes a subset of the project with the workspace and everything it
+/// transitively depends on. Returns names of the written workspaces.
+pub fn prune(project: &Project, options: &PruneOptions) -> Result<Vec<String>, String> {
+    if !project.workspaces.contains_key(&options.workspace) {
+        return Err(format!("Unknown workspace \"{}\"", options.workspace));
+    }
+
+    let out_dir = Path::new(&project.path).join(&options.out_dir);
+    if out_dir.exists() {
+        return Err(format!(
+            "Output directory {} already exists",
+            out_dir.display()
+        ));
+    }
+
+    let graph = DepGraph::new(project.workspaces.values().cloned().collect());
+    let mut selected = graph.dependencies(&options.workspace, true);
+    selected.insert(options.workspace.clone());
+    let names = graph.top_sort(selected)?;
+    let workspaces: Vec<&Workspace> = names
+        .iter()
+        .filter_map(|name| project.workspaces.get(name))
+        .collect();
+
+    // Workspaces nested in kept ones and the output itself stay out.
+    let mut skip: Vec<PathBuf> = project
+        .workspaces
+        .values()
+        .filter(|ws| !names.contains(&ws.name))
+        .map(|ws| PathBuf::from(&ws.path))
+        .collect();
+    skip.push(out_dir.clone());
+
+    let package_json = root_package_json(project, &workspaces)?;
+    let lockfile = match Lockfile::detect(&project.path) {
+        Some(lockfile) => {
+            let lockfile = lockfile?;
+            let mut importers = vec![(String::new(), project.package_json())];
+            for ws in &workspaces {
+                importers.push((ws.relative_path(&project.path), &ws.package_json));
+            }
+            Some((lockfile.file_name().to_owned(), lockfile.prune(&importers)?))
+        }
+        None => None,
+    };
+
+    let layers = if options.docker {
+        vec![(out_dir.join("json"), false), (out_dir.join("full"), true)]
+    } else {
+        vec![(out_dir, true)]
+    };
+
+    for (dir, with_sources) in layers {
+        create_dir(&dir)?;
+        write(&dir.join("package.json"), &package_json)?;
+        if let Some((file_name, contents)) = &lockfile {
+            write(&dir.join(file_name), contents)?;
+        }
+        for file_name in ROOT_FILES {
+            let path = Path::new(&project.path).join(file_name);
+            if path.is_file() {
+                copy(&path, &dir.join(file_name))?;
+            }
+        }
+
+        for ws in &workspaces {
+            let ws_dir = dir.join(ws.relative_path(&project.path));
+            if with_sources {
+                copy_dir(Path::new(&ws.path), &ws_dir, &skip)?;
+            } else {
+                create_dir(&ws_dir)?;
+                copy(
+                    Path::new(&ws.package_json.path),
+                    &ws_dir.join("package.json"),
+                )?;
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// The root `package.json` with `workspaces` listing only the kept ones.
+fn root_package_json(project: &Project, workspaces: &[&Workspace]) -> Result<String, String> {
+    let path = &project.package_json().path;
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let mut package_json: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Couldn't parse {}: {}", path, e))?;
+
+    let mut ws_paths: Vec<String> = workspaces
+        .iter()
+        .map(|ws| ws.relative_path(&project.path))
+        .filter(|path| !path.is_empty())
+        .collect();
+    ws_paths.sort();
+    let ws_paths = Value::from(ws_paths);
+
+    match package_json.get_mut("workspaces") {
+        // Yarn's `{ "packages": [...], "nohoist": [...] }` form.
+        Some(Value::Object(config)) => {
+            config.insert("packages".to_owned(), ws_paths);
+        }
+        Some(workspaces) => *workspaces = ws_paths,
+        None => {}
+    }
+
+    let mut result = serde_json::to_string_pretty(&package_json).map_err(|e| e.to_string())?;
+    result.push('\n');
+    Ok(result)
+}
+
+/// Copies the directory without `node_modules`, `.git` and `.cache`, nor the
+/// directories in `skip`.
+pub(crate) fn copy_dir(from: &Path, to: &Path, skip: &[PathBuf]) -> Result<(), String> {
+    create_dir(to)?;
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Couldn't read {}: {}", from.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Couldn't read {}: {}", from.display(), e))?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+
+        if path.is_dir() {
+            let skipped = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| SKIP_DIRS.contains(&name));
+            if !skipped && !skip.contains(&path) {
+                copy_dir(&path, &target, skip)?;
+            }
+        } else if path.is_file() {
+            copy(&path, &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn create_dir(path: &Path) -> Result<(), String> {
+    fs::create_dir_all(path).map_err(|e| format!("Couldn't create {}: {}", path.display(), e))
+}
+
+fn copy(from: &Path, to: &Path) -> Result<(), String> {
+    fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't copy {}: {}", from.display(), e))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+}
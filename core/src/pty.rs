@@ -0,0 +1,87 @@
+//! Pseudo-terminals for task processes, so tools keep their colors.
+
+use std::fs::File;
+use std::io;
+
+/// Width of the terminal tasks get when ours has none, e.g. in CI.
+#[cfg(unix)]
+const DEFAULT_COLUMNS: u16 = 120;
+
+/// Both ends of a pseudo-terminal. The process writes to `slave` as its
+/// stdout and stderr, whatever it writes is read from `master`.
+pub struct Pty {
+    pub master: File,
+    pub slave: File,
+}
+
+/// A pseudo-terminal as wide as ours. Both ends are closed on exec, only
+/// the process they're passed to as stdio keeps the slave open.
+#[cfg(unix)]
+pub fn open() -> io::Result<Pty> {
+    use std::os::unix::io::FromRawFd;
+
+    let mut master = 0;
+    let mut slave = 0;
+    let mut size = window_size();
+    // Safety: the out pointers are valid for the call, the name and termios
+    // are optional.
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::addr_of_mut!(size),
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Safety: openpty just opened them and nothing else owns them.
+    let pty = unsafe {
+        Pty {
+            master: File::from_raw_fd(master),
+            slave: File::from_raw_fd(slave),
+        }
+    };
+    set_cloexec(master)?;
+    set_cloexec(slave)?;
+
+    Ok(pty)
+}
+
+#[cfg(not(unix))]
+pub fn open() -> io::Result<Pty> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "pseudo-terminals are only supported on unix",
+    ))
+}
+
+#[cfg(unix)]
+fn window_size() -> libc::winsize {
+    let mut size = libc::winsize {
+        ws_row: 24,
+        ws_col: DEFAULT_COLUMNS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let mut ours = size;
+    // Safety: TIOCGWINSZ only writes a winsize.
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ours) };
+    if result == 0 && ours.ws_col > 0 {
+        size = ours;
+    }
+    size
+}
+
+#[cfg(unix)]
+fn set_cloexec(fd: libc::c_int) -> io::Result<()> {
+    // Safety: fcntl on a descriptor we own.
+    let result = unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,290 @@
+//! Publishing workspaces to an npm registry in dependency order.
+
+use super::dep_spec::{DepSpec, WorkspaceRange};
+use super::dependency_graph::DepGraph;
+use super::project::Project;
+use super::prune::copy_dir;
+use super::workspace::Workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Sections of the packed manifest `workspace:` specs are rewritten in.
+const DEPENDENCY_SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Registry to check and publish to, npm's configuration when `None`.
+    pub registry: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishStatus {
+    Published,
+    /// `name@version` was already in the registry.
+    AlreadyPublished,
+    Failed,
+    /// A dependency failed to publish.
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PublishResult {
+    pub name: String,
+    pub version: String,
+    pub status: PublishStatus,
+    /// Set for failed and skipped workspaces.
+    pub error: Option<String>,
+}
+
+/// Publishes the versions of non-private workspaces missing from the
+/// registry, dependencies first. Workspaces depending on one that failed,
+/// directly or not, are skipped. Returns a result per workspace, in the
+/// order they were published.
+pub fn publish(project: &Project, options: &PublishOptions) -> Result<Vec<PublishResult>, String> {
+    let graph = DepGraph::new(project.workspaces.values().cloned().collect());
+    let versions: HashMap<&str, &str> = project
+        .workspaces
+        .values()
+        .map(|ws| (ws.name.as_str(), ws.package_json.version.as_str()))
+        .collect();
+
+    let mut failed: HashSet<String> = HashSet::new();
+    let mut results = vec![];
+
+    for name in graph.top_sort(graph.workspace_names())? {
+        let ws = &project.workspaces[&name];
+        if ws.package_json.private {
+            continue;
+        }
+
+        let mut failed_dependencies: Vec<String> = graph
+            .dependencies(&name, true)
+            .into_iter()
+            .filter(|dep| failed.contains(dep))
+            .collect();
+        failed_dependencies.sort();
+
+        let (status, error) = if let Some(dep) = failed_dependencies.first() {
+            (
+                PublishStatus::Skipped,
+                Some(format!("Dependency \"{}\" wasn't published", dep)),
+            )
+        } else {
+            match publish_workspace(&project.path, ws, &versions, options) {
+                Ok(true) => (PublishStatus::Published, None),
+                Ok(false) => (PublishStatus::AlreadyPublished, None),
+                Err(e) => (PublishStatus::Failed, Some(e)),
+            }
+        };
+        if error.is_some() {
+            failed.insert(name.clone());
+        }
+
+        results.push(PublishResult {
+            version: ws.package_json.version.clone(),
+            name,
+            status,
+            error,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Returns whether the workspace was published, `false` when its version
+/// already was. Registry commands run in the root without workspace
+/// support: npm would otherwise publish the workspace directory instead of
+/// the tarball, and the root `.npmrc` still applies.
+fn publish_workspace(
+    root: &str,
+    ws: &Workspace,
+    versions: &HashMap<&str, &str>,
+    options: &PublishOptions,
+) -> Result<bool, String> {
+    let package = format!("{}@{}", ws.name, ws.package_json.version);
+    if is_published(root, &package, options)? {
+        return Ok(false);
+    }
+
+    let dir = std::env::temp_dir().join(format!("sculpture-publish-{}", std::process::id()));
+    let published = pack(ws, versions, &dir).and_then(|tarball| {
+        let tarball_path = tarball.to_string_lossy().into_owned();
+        npm(
+            root,
+            &["publish", tarball_path.as_str(), "--no-workspaces"],
+            options,
+        )
+    });
+    let _ = fs::remove_dir_all(&dir);
+    published?;
+
+    Ok(true)
+}
+
+fn is_published(root: &str, package: &str, options: &PublishOptions) -> Result<bool, String> {
+    let output = npm_output(
+        root,
+        &["view", package, "version", "--no-workspaces"],
+        options,
+    )?;
+    if output.status.success() {
+        // An existing package without that version prints nothing.
+        return Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("E404") {
+        Ok(false)
+    } else {
+        Err(format!(
+            "Couldn't check if {} is published: {}",
+            package,
+            npm_error(&stderr)
+        ))
+    }
+}
+
+/// Packs a copy of the workspace staged in `dir`, with `workspace:` specs
+/// replaced by the versions they resolve to, so the workspace itself is
+/// never touched. The tarball goes to `dir` too.
+fn pack(ws: &Workspace, versions: &HashMap<&str, &str>, dir: &Path) -> Result<PathBuf, String> {
+    let manifest_path = &ws.package_json.path;
+    let original = fs::read_to_string(manifest_path)
+        .map_err(|e| format!("Couldn't read {}: {}", manifest_path, e))?;
+    let packed = packed_manifest(&original, versions)
+        .map_err(|e| format!("Couldn't parse {}: {}", manifest_path, e))?;
+
+    let staging = dir.join("package");
+    copy_dir(Path::new(&ws.path), &staging, &[])?;
+    if let Some(packed) = packed {
+        let staged_manifest = staging.join("package.json");
+        fs::write(&staged_manifest, packed)
+            .map_err(|e| format!("Couldn't write {}: {}", staged_manifest.display(), e))?;
+    }
+
+    let dir_path = dir.to_string_lossy().into_owned();
+    let output = npm_output(
+        &staging.to_string_lossy(),
+        &["pack", "--json", "--pack-destination", dir_path.as_str()],
+        &PublishOptions::default(),
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "npm pack failed: {}",
+            npm_error(&String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+
+    let packed: Vec<PackedTarball> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Couldn't parse the output of npm pack: {}", e))?;
+    match packed.into_iter().next() {
+        Some(tarball) => Ok(dir.join(tarball.filename)),
+        None => Err("npm pack didn't create a tarball".to_owned()),
+    }
+}
+
+/// An entry of `npm pack --json`, the rest of it is of no use here.
+#[derive(Deserialize)]
+struct PackedTarball {
+    filename: String,
+}
+
+/// The manifest with `workspace:` specs replaced, `None` when it has none.
+/// `workspace:*` becomes the exact version, `workspace:^` and `workspace:~`
+/// the version with that prefix and other ranges lose the protocol.
+fn packed_manifest(
+    contents: &str,
+    versions: &HashMap<&str, &str>,
+) -> Result<Option<String>, String> {
+    let mut manifest: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let mut rewritten = false;
+
+    for section in DEPENDENCY_SECTIONS {
+        let deps = match manifest.get_mut(*section).and_then(Value::as_object_mut) {
+            Some(deps) => deps,
+            None => continue,
+        };
+        for (name, spec) in deps.iter_mut() {
+            let raw = match spec.as_str() {
+                Some(raw) => raw,
+                None => continue,
+            };
+            let range = match DepSpec::parse(raw) {
+                Ok(DepSpec::Workspace(range)) => range,
+                _ => continue,
+            };
+            let version = versions
+                .get(name.as_str())
+                .ok_or_else(|| format!("\"{}\" isn't a workspace", name))?;
+            let concrete = match range {
+                WorkspaceRange::Any => version.to_string(),
+                WorkspaceRange::Caret => format!("^{}", version),
+                WorkspaceRange::Tilde => format!("~{}", version),
+                WorkspaceRange::Range(_) => raw.trim()["workspace:".len()..].to_owned(),
+            };
+            *spec = Value::from(concrete);
+            rewritten = true;
+        }
+    }
+
+    if !rewritten {
+        return Ok(None);
+    }
+    let mut result = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    result.push('\n');
+    Ok(Some(result))
+}
+
+/// Runs npm in the directory, failing with its error output.
+fn npm(dir: &str, args: &[&str], options: &PublishOptions) -> Result<(), String> {
+    let output = npm_output(dir, args, options)?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "npm {} failed: {}",
+            args[0],
+            npm_error(&String::from_utf8_lossy(&output.stderr))
+        ))
+    }
+}
+
+fn npm_output(dir: &str, args: &[&str], options: &PublishOptions) -> Result<Output, String> {
+    let mut command = Command::new("npm");
+    command.args(args).current_dir(Path::new(dir));
+    if let Some(registry) = &options.registry {
+        command.arg("--registry").arg(registry);
+    }
+
+    command
+        .output()
+        .map_err(|e| format!("Couldn't run npm: {}", e))
+}
+
+/// The error lines of npm's output, without notices and the log file path.
+fn npm_error(stderr: &str) -> String {
+    let errors: Vec<&str> = stderr
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("npm error ")
+                .or_else(|| line.strip_prefix("npm ERR! "))
+        })
+        .filter(|line| !line.starts_with("A complete log of this run"))
+        .collect();
+
+    if errors.is_empty() {
+        stderr.trim().to_owned()
+    } else {
+        errors.join("\n")
+    }
+}
@@ -0,0 +1,185 @@
+//! New versions planned from changesets, and writing them out.
+
+use super::changelog;
+use super::changeset::{Bump, Changeset};
+use super::dep_spec::{spec_for_version, DepSpec};
+use super::dependency_graph::DepGraph;
+use super::project::Project;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Release {
+    pub name: String,
+    pub old_version: String,
+    pub new_version: String,
+    pub bump: Bump,
+    /// Changesets releasing the workspace, empty when it's only released for
+    /// its dependencies.
+    pub changes: Vec<Change>,
+    /// Ranges on released workspaces the new versions would break.
+    pub dependency_updates: Vec<DependencyUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Change {
+    /// Id of the changeset.
+    pub id: String,
+    /// What the changeset bumps this workspace by.
+    pub bump: Bump,
+    pub summary: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DependencyUpdate {
+    /// Key in `dependencies`.
+    pub name: String,
+    /// Name of the released workspace, differs from `name` for aliases.
+    pub package: String,
+    /// Its new version.
+    pub version: String,
+    pub old_spec: String,
+    pub new_spec: String,
+}
+
+/// Releases in dependency order. Each workspace gets the highest bump of
+/// its changesets. Dependents whose range doesn't match a new version get
+/// the range rewritten and at least a patch bump, which propagates the same
+/// way.
+pub fn plan(project: &Project, changesets: &[Changeset]) -> Result<Vec<Release>, String> {
+    let mut bumps: HashMap<String, Bump> = HashMap::new();
+    let mut changes: HashMap<String, Vec<Change>> = HashMap::new();
+    for changeset in changesets {
+        for (name, bump) in &changeset.releases {
+            if !project.workspaces.contains_key(name) {
+                return Err(format!(
+                    "Changeset \"{}\" releases unknown workspace \"{}\"",
+                    changeset.id, name
+                ));
+            }
+            let entry = bumps.entry(name.clone()).or_insert(*bump);
+            *entry = (*entry).max(*bump);
+            if !changeset.summary.is_empty() {
+                changes.entry(name.clone()).or_default().push(Change {
+                    id: changeset.id.clone(),
+                    bump: *bump,
+                    summary: changeset.summary.clone(),
+                });
+            }
+        }
+    }
+
+    let graph = DepGraph::new(project.workspaces.values().cloned().collect());
+    let mut updates: HashMap<String, Vec<DependencyUpdate>> = HashMap::new();
+    let mut releases = vec![];
+
+    for name in graph.top_sort(graph.workspace_names())? {
+        let bump = match bumps.get(&name) {
+            Some(bump) => *bump,
+            None => continue,
+        };
+        let old_version = &project.workspaces[&name].package_json.version;
+        let new_version = bumped(old_version, bump)
+            .map_err(|e| format!("Package \"{}\" has an invalid version: {}", name, e))?;
+
+        let mut dependents: Vec<String> = graph.dependents(&name, false).into_iter().collect();
+        dependents.sort();
+        for dependent in dependents {
+            let ws = &project.workspaces[&dependent];
+            for (key, spec) in &ws.package_json.dependencies {
+                let breaks = DepSpec::parse(spec).is_ok_and(|parsed| {
+                    parsed.package_name(key) == name && parsed.matches(&new_version) == Some(false)
+                });
+                if breaks {
+                    updates
+                        .entry(dependent.clone())
+                        .or_default()
+                        .push(DependencyUpdate {
+                            name: key.clone(),
+                            package: name.clone(),
+                            version: new_version.to_string(),
+                            old_spec: spec.clone(),
+                            new_spec: spec_for_version(spec, &new_version),
+                        });
+                    bumps.entry(dependent.clone()).or_insert(Bump::Patch);
+                }
+            }
+        }
+
+        releases.push(Release {
+            old_version: old_version.clone(),
+            new_version: new_version.to_string(),
+            bump,
+            changes: changes.remove(&name).unwrap_or_default(),
+            dependency_updates: vec![],
+            name,
+        });
+    }
+
+    for release in &mut releases {
+        let mut dependency_updates = updates.remove(&release.name).unwrap_or_default();
+        dependency_updates.sort_by(|a, b| a.name.cmp(&b.name));
+        release.dependency_updates = dependency_updates;
+    }
+
+    Ok(releases)
+}
+
+/// Rewrites versions and ranges in the released workspaces' `package.json`
+/// files, prepends their changelog entries and removes the changesets. All
+/// edits are made before anything is written, so a manifest that doesn't
+/// match the plan leaves the project as it was.
+pub fn apply(
+    project: &Project,
+    releases: &[Release],
+    changesets: &[Changeset],
+) -> Result<(), String> {
+    let mut manifests = vec![];
+    let mut changelogs = vec![];
+    for release in releases {
+        let ws = &project.workspaces[&release.name];
+        let mut manifest = ws.package_json.edit()?;
+        manifest.set_version(&release.old_version, &release.new_version)?;
+        for update in &release.dependency_updates {
+            manifest.set_dependency_spec(&update.name, &update.old_spec, &update.new_spec)?;
+        }
+        manifests.push(manifest);
+        changelogs.push(changelog::prepended(&ws.path, release)?);
+    }
+
+    for manifest in &manifests {
+        manifest.write()?;
+    }
+    for (path, contents) in &changelogs {
+        fs::write(path, contents)
+            .map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+    }
+    for changeset in changesets {
+        fs::remove_file(&changeset.path)
+            .map_err(|e| format!("Couldn't remove {}: {}", changeset.path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Applies the pending changesets of the project.
+pub fn version(project: &Project) -> Result<Vec<Release>, String> {
+    let changesets = Changeset::read_all(&project.path)?;
+    let releases = plan(project, &changesets)?;
+    apply(project, &releases, &changesets)?;
+
+    Ok(releases)
+}
+
+fn bumped(version: &str, bump: Bump) -> Result<Version, String> {
+    let mut version = Version::parse(version).map_err(|e| format!("\"{}\": {}", version, e))?;
+    match bump {
+        Bump::Major => version.increment_major(),
+        Bump::Minor => version.increment_minor(),
+        Bump::Patch => version.increment_patch(),
+    }
+
+    Ok(version)
+}
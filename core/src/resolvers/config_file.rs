@@ -0,0 +1,57 @@
+use super::gitignore::walk;
+use super::{InputResolver, ResolveFuture};
+use futures::future::{self, FutureExt};
+use std::path::Path;
+
+/// For tools configured by a file but without a notion of inputs: every file
+/// the gitignore resolver finds, minus the tool's default output directories.
+pub struct ConfigFileResolver {
+    name: &'static str,
+    config_files: &'static [&'static str],
+    output_dirs: &'static [&'static str],
+}
+
+pub const BABEL: ConfigFileResolver = ConfigFileResolver {
+    name: "babel",
+    config_files: &[
+        "babel.config.js",
+        "babel.config.cjs",
+        "babel.config.mjs",
+        "babel.config.json",
+        ".babelrc",
+        ".babelrc.js",
+        ".babelrc.cjs",
+        ".babelrc.mjs",
+        ".babelrc.json",
+    ],
+    output_dirs: &[],
+};
+
+pub const VITE: ConfigFileResolver = ConfigFileResolver {
+    name: "vite",
+    config_files: &[
+        "vite.config.js",
+        "vite.config.cjs",
+        "vite.config.mjs",
+        "vite.config.ts",
+        "vite.config.cts",
+        "vite.config.mts",
+    ],
+    output_dirs: &["dist"],
+};
+
+impl InputResolver for ConfigFileResolver {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn applies(&self, ws_path: &str) -> bool {
+        self.config_files
+            .iter()
+            .any(|file| Path::new(ws_path).join(file).is_file())
+    }
+
+    fn resolve(&self, ws_path: &str) -> ResolveFuture {
+        future::ready(walk(ws_path, self.output_dirs)).boxed()
+    }
+}
@@ -0,0 +1,62 @@
+use super::{InputResolver, ResolveFuture};
+use futures::future::{self, FutureExt};
+use ignore::WalkBuilder;
+use std::path::Path;
+
+/// Tool specific ignore file, same syntax as `.gitignore`.
+const IGNORE_FILE: &str = ".sculptureignore";
+
+/// Every file in the workspace directory that isn't ignored by `.gitignore`,
+/// `.ignore` or `.sculptureignore`, including the ones in parent directories.
+/// Nested packages, anything with its own `package.json`, and
+/// `node_modules` are skipped.
+pub struct GitignoreResolver;
+
+impl InputResolver for GitignoreResolver {
+    fn name(&self) -> &str {
+        "gitignore"
+    }
+
+    fn applies(&self, ws_path: &str) -> bool {
+        Path::new(ws_path).is_dir()
+    }
+
+    fn resolve(&self, ws_path: &str) -> ResolveFuture {
+        future::ready(walk(ws_path, &[])).boxed()
+    }
+}
+
+/// Files not ignored in the workspace, not descending into `skip_dirs`
+/// directly inside it.
+pub fn walk(ws_path: &str, skip_dirs: &[&str]) -> Result<Vec<String>, String> {
+    let root = Path::new(ws_path).to_owned();
+    let skip: Vec<_> = skip_dirs.iter().map(|dir| root.join(dir)).collect();
+    let walker = WalkBuilder::new(&root)
+        .hidden(false)
+        .require_git(false)
+        .git_global(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            let path = entry.path();
+            if path == root || !entry.file_type().is_some_and(|t| t.is_dir()) {
+                return true;
+            }
+            let name = entry.file_name();
+            name != "node_modules"
+                && name != ".git"
+                && !skip.iter().any(|dir| dir == path)
+                && !path.join("package.json").is_file()
+        })
+        .build();
+
+    let mut files = vec![];
+    for entry in walker {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.file_type().is_some_and(|t| t.is_file()) {
+            files.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
@@ -0,0 +1,109 @@
+//! Input resolvers: what files a workspace's outputs depend on.
+
+mod config_file;
+mod gitignore;
+mod node;
+mod tsconfig;
+
+pub use config_file::{BABEL, VITE};
+use futures::future::BoxFuture;
+pub use gitignore::GitignoreResolver;
+pub use node::NodeResolver;
+use std::sync::Arc;
+pub use tsconfig::TsconfigResolver;
+
+/// Absolute paths of the input files.
+pub type ResolveFuture = BoxFuture<'static, Result<Vec<String>, String>>;
+
+/// Resolves the files a workspace's outputs depend on.
+pub trait InputResolver: Send + Sync {
+    /// What workspaces select the resolver by in the `resolvers` config.
+    fn name(&self) -> &str;
+
+    /// Whether the workspace has the files the resolver works off, which
+    /// selects it for workspaces that don't configure resolvers.
+    fn applies(&self, ws_path: &str) -> bool;
+
+    /// Inputs of the workspace at `ws_path`.
+    fn resolve(&self, ws_path: &str) -> ResolveFuture;
+}
+
+pub type SharedResolver = Arc<dyn InputResolver>;
+
+/// The resolvers a run picks from for each workspace.
+#[derive(Clone)]
+pub struct ResolverRegistry {
+    resolvers: Vec<SharedResolver>,
+    /// Used for workspaces that neither configure resolvers nor get any
+    /// inputs from the detected ones.
+    fallback: SharedResolver,
+}
+
+/// The resolvers picked for a workspace.
+pub struct Selection {
+    pub resolvers: Vec<SharedResolver>,
+    pub fallback: Option<SharedResolver>,
+}
+
+impl ResolverRegistry {
+    /// The native resolvers, with the gitignore one as the fallback.
+    pub fn builtin() -> Self {
+        Self {
+            resolvers: vec![Arc::new(TsconfigResolver), Arc::new(BABEL), Arc::new(VITE)],
+            fallback: Arc::new(GitignoreResolver),
+        }
+    }
+
+    /// Replaces a resolver registered under the same name.
+    pub fn register(&mut self, resolver: SharedResolver) {
+        self.resolvers.retain(|r| r.name() != resolver.name());
+        self.resolvers.push(resolver);
+    }
+
+    /// Resolvers named in the workspace config, or else in the root one, or
+    /// else the ones whose files the workspace has. Their results are merged.
+    pub fn select(
+        &self,
+        ws_path: &str,
+        ws_names: &[String],
+        root_names: &[String],
+    ) -> Result<Selection, String> {
+        let names = if ws_names.is_empty() {
+            root_names
+        } else {
+            ws_names
+        };
+        if names.is_empty() {
+            return Ok(Selection {
+                resolvers: self
+                    .resolvers
+                    .iter()
+                    .filter(|r| r.applies(ws_path))
+                    .cloned()
+                    .collect(),
+                fallback: Some(Arc::clone(&self.fallback)),
+            });
+        }
+
+        let resolvers = names
+            .iter()
+            .map(|name| {
+                self.resolvers
+                    .iter()
+                    .chain(std::iter::once(&self.fallback))
+                    .find(|r| r.name() == name)
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown input resolver \"{}\"", name))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Selection {
+            resolvers,
+            fallback: None,
+        })
+    }
+
+    /// Used when a workspace's configured resolvers can't be selected.
+    pub fn fallback(&self) -> SharedResolver {
+        Arc::clone(&self.fallback)
+    }
+}
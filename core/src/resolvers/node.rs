@@ -0,0 +1,94 @@
+use super::{InputResolver, ResolveFuture};
+use futures::channel::oneshot;
+use futures::future::FutureExt;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::process::Command;
+use std::sync::Arc;
+
+/// Calls `inputResolver(wsPath)` of every plugin module and prints the
+/// files they resolved as a JSON list.
+const BRIDGE: &str = r#"
+const [wsPath, ...plugins] = process.argv.slice(1);
+Promise.all(
+  plugins.map((path) => {
+    const plugin = require(path);
+    const resolver = plugin.inputResolver || (plugin.default && plugin.default.inputResolver);
+    return resolver ? resolver(wsPath) : [];
+  })
+)
+  .then((results) => process.stdout.write(JSON.stringify([...new Set(results.flat())])))
+  .catch((e) => {
+    console.error((e && e.stack) || e);
+    process.exit(1);
+  });
+"#;
+
+/// The "js" resolver for the native CLI: instead of calling into the Node
+/// process that loaded the addon, spawns `node` for every workspace and
+/// runs the plugin modules there. The processes are waited for on a pool,
+/// as many at once as there are threads.
+pub struct NodeResolver {
+    plugins: Vec<String>,
+    pool: Arc<ThreadPool>,
+}
+
+impl NodeResolver {
+    /// `plugins` are paths to CommonJS modules, resolved by `require`.
+    pub fn new(plugins: Vec<String>) -> Result<Self, String> {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("sculpture-node-{}", i))
+            .build()
+            .map(|pool| Self {
+                plugins,
+                pool: Arc::new(pool),
+            })
+            .map_err(|e| format!("Couldn't start the plugin pool: {}", e))
+    }
+}
+
+impl InputResolver for NodeResolver {
+    fn name(&self) -> &str {
+        "js"
+    }
+
+    /// Only registered when there are plugins, which decide for themselves.
+    fn applies(&self, _ws_path: &str) -> bool {
+        true
+    }
+
+    fn resolve(&self, ws_path: &str) -> ResolveFuture {
+        let mut command = Command::new("node");
+        command
+            .arg("-e")
+            .arg(BRIDGE)
+            .arg(ws_path)
+            .args(&self.plugins);
+        let pool = Arc::clone(&self.pool);
+
+        async move {
+            let (sender, receiver) = oneshot::channel();
+            pool.spawn(move || {
+                // The run may have stopped waiting, nothing to do about it.
+                let _ = sender.send(command.output());
+            });
+
+            match receiver.await {
+                Ok(Ok(output)) if output.status.success() => {
+                    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+                }
+                Ok(Ok(output)) => Err(format!(
+                    "JS plugins failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Ok(Err(e)) => Err(format!("Couldn't start node: {}", e)),
+                Err(_) => Err("The plugin pool dropped the workspace".to_owned()),
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,403 @@
+use super::{InputResolver, ResolveFuture};
+use crate::paths::normalize;
+use futures::future::{self, FutureExt};
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const TSCONFIG: &str = "tsconfig.json";
+
+/// Excluded when a config doesn't set `exclude`, same as in tsc.
+const DEFAULT_EXCLUDE: &[&str] = &["node_modules", "bower_components", "jspm_packages"];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Inputs of a workspace with a `tsconfig.json`: the files its `files` and
+/// `include` select minus `exclude`, following `extends` and the
+/// `references` inside the workspace, plus every config read on the way.
+///
+/// Unlike tsc, `include` globs match files of any extension, so assets
+/// imported from sources count as inputs too.
+pub struct TsconfigResolver;
+
+impl InputResolver for TsconfigResolver {
+    fn name(&self) -> &str {
+        "tsconfig"
+    }
+
+    fn applies(&self, ws_path: &str) -> bool {
+        Path::new(ws_path).join(TSCONFIG).is_file()
+    }
+
+    fn resolve(&self, ws_path: &str) -> ResolveFuture {
+        future::ready(resolve(ws_path)).boxed()
+    }
+}
+
+fn resolve(ws_path: &str) -> Result<Vec<String>, String> {
+    let root = Path::new(ws_path);
+    let mut inputs = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    collect(&root.join(TSCONFIG), root, &mut visited, &mut inputs)?;
+
+    Ok(inputs
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsconfigJson {
+    extends: Option<Extends>,
+    files: Option<Vec<String>>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+
+    #[serde(default)]
+    references: Vec<Reference>,
+
+    #[serde(default)]
+    compiler_options: CompilerOptions,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Extends {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Reference {
+    path: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptions {
+    out_dir: Option<String>,
+}
+
+/// A config with its `extends` chain applied. Paths are kept together with
+/// the directory of the config that set them, as that's what they're
+/// relative to.
+#[derive(Debug, Default)]
+struct Tsconfig {
+    files: Option<(PathBuf, Vec<String>)>,
+    include: Option<(PathBuf, Vec<String>)>,
+    exclude: Option<(PathBuf, Vec<String>)>,
+    out_dir: Option<PathBuf>,
+    references: Vec<PathBuf>,
+    configs: Vec<PathBuf>,
+}
+
+fn collect(
+    config_path: &Path,
+    ws_root: &Path,
+    visited: &mut BTreeSet<PathBuf>,
+    inputs: &mut BTreeSet<PathBuf>,
+) -> Result<(), String> {
+    if !visited.insert(config_path.to_owned()) {
+        return Ok(());
+    }
+
+    let config = load(config_path, &mut vec![])?;
+    let config_dir = parent(config_path);
+    inputs.extend(
+        config
+            .configs
+            .iter()
+            .filter(|path| !is_in_node_modules(path))
+            .cloned(),
+    );
+
+    if let Some((base, files)) = &config.files {
+        for file in files {
+            let path = normalize(&base.join(file));
+            if path.is_file() {
+                inputs.insert(path);
+            }
+        }
+    }
+
+    let (include_base, include) = match (&config.include, &config.files) {
+        (Some((base, include)), _) => (base.clone(), include.clone()),
+        (None, Some(_)) => (config_dir.clone(), vec![]),
+        (None, None) => (config_dir.clone(), vec!["**/*".to_owned()]),
+    };
+    let exclude = match &config.exclude {
+        Some((base, exclude)) => patterns(base, exclude)?,
+        None => {
+            let mut exclude = patterns(&config_dir, DEFAULT_EXCLUDE)?;
+            if let Some(out_dir) = &config.out_dir {
+                exclude.extend(patterns(out_dir, &["."])?);
+            }
+            exclude
+        }
+    };
+
+    for (walk_root, pattern) in include_patterns(&include_base, &include)? {
+        walk(&walk_root, &exclude, &mut |path| {
+            if pattern.matches_path_with(path, MATCH_OPTIONS) {
+                inputs.insert(path.to_owned());
+            }
+        });
+    }
+
+    for reference in &config.references {
+        let reference = if reference.is_dir() {
+            reference.join(TSCONFIG)
+        } else {
+            reference.clone()
+        };
+        // References to other workspaces are dependency graph edges.
+        if reference.starts_with(ws_root) && reference.is_file() {
+            collect(&reference, ws_root, visited, inputs)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn load(config_path: &Path, chain: &mut Vec<PathBuf>) -> Result<Tsconfig, String> {
+    if chain.iter().any(|path| path == config_path) {
+        return Err(format!(
+            "Circular extends in {}",
+            chain[0].to_string_lossy()
+        ));
+    }
+    chain.push(config_path.to_owned());
+
+    let contents = fs::read_to_string(config_path)
+        .map_err(|e| format!("Couldn't read {}: {}", config_path.display(), e))?;
+    let json: TsconfigJson = serde_json::from_str(&strip_jsonc(&contents))
+        .map_err(|e| format!("Couldn't parse {}: {}", config_path.display(), e))?;
+    let dir = parent(config_path);
+
+    let extends = match json.extends {
+        Some(Extends::One(path)) => vec![path],
+        Some(Extends::Many(paths)) => paths,
+        None => vec![],
+    };
+    let mut config = Tsconfig::default();
+    for spec in extends {
+        // tsc reports these too but still compiles the rest.
+        let path = match resolve_extends(&dir, &spec) {
+            Some(path) => path,
+            None => {
+                eprintln!(
+                    "Couldn't resolve \"{}\" extended in {}, ignoring it",
+                    spec,
+                    config_path.display()
+                );
+                continue;
+            }
+        };
+        let base = load(&path, chain)?;
+        config.files = base.files.or(config.files);
+        config.include = base.include.or(config.include);
+        config.exclude = base.exclude.or(config.exclude);
+        config.out_dir = base.out_dir.or(config.out_dir);
+        config.configs.extend(base.configs);
+    }
+    chain.pop();
+
+    if let Some(files) = json.files {
+        config.files = Some((dir.clone(), files));
+    }
+    if let Some(include) = json.include {
+        config.include = Some((dir.clone(), include));
+    }
+    if let Some(exclude) = json.exclude {
+        config.exclude = Some((dir.clone(), exclude));
+    }
+    if let Some(out_dir) = json.compiler_options.out_dir {
+        config.out_dir = Some(normalize(&dir.join(out_dir)));
+    }
+    config.references = json
+        .references
+        .iter()
+        .map(|reference| normalize(&dir.join(&reference.path)))
+        .collect();
+    config.configs.push(config_path.to_owned());
+
+    Ok(config)
+}
+
+/// Relative specifiers are paths, anything else is looked up in
+/// `node_modules` the way tsc does.
+fn resolve_extends(dir: &Path, spec: &str) -> Option<PathBuf> {
+    let candidates = |path: PathBuf| {
+        let mut with_json = path.clone().into_os_string();
+        with_json.push(".json");
+        vec![path.clone(), PathBuf::from(with_json), path.join(TSCONFIG)]
+    };
+
+    if spec.starts_with('.') || Path::new(spec).is_absolute() {
+        return candidates(normalize(&dir.join(spec)))
+            .into_iter()
+            .find(|path| path.is_file());
+    }
+
+    dir.ancestors()
+        .flat_map(|ancestor| candidates(ancestor.join("node_modules").join(spec)))
+        .find(|path| path.is_file())
+}
+
+/// Turns `include` entries into a directory to walk and a pattern to match
+/// files in it against. Entries without a wildcard or an extension in the
+/// last segment are directories and include everything inside.
+fn include_patterns(base: &Path, include: &[String]) -> Result<Vec<(PathBuf, Pattern)>, String> {
+    let mut result = vec![];
+    for entry in include {
+        let path = normalize(&base.join(entry));
+        let last = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = if has_wildcard(&last) || last.contains('.') {
+            path
+        } else {
+            path.join("**/*")
+        };
+
+        let walk_root: PathBuf = path
+            .components()
+            .take_while(|c| !has_wildcard(&c.as_os_str().to_string_lossy()))
+            .collect();
+        let walk_root = if walk_root == path {
+            parent(&path)
+        } else {
+            walk_root
+        };
+        result.push((walk_root, pattern(&path)?));
+    }
+    Ok(result)
+}
+
+fn patterns(base: &Path, entries: &[impl AsRef<str>]) -> Result<Vec<Pattern>, String> {
+    entries
+        .iter()
+        .map(|entry| pattern(&normalize(&base.join(entry.as_ref()))))
+        .collect()
+}
+
+/// The literal part of the path is escaped so directories with glob
+/// characters in their names still match.
+fn pattern(path: &Path) -> Result<Pattern, String> {
+    let mut literal = true;
+    let segments: Vec<String> = path
+        .components()
+        .map(|c| {
+            if c == Component::RootDir {
+                return String::new();
+            }
+            let segment = c.as_os_str().to_string_lossy();
+            literal = literal && !has_wildcard(&segment);
+            if literal {
+                Pattern::escape(&segment)
+            } else {
+                segment.into_owned()
+            }
+        })
+        .collect();
+    let pattern = segments.join("/");
+    Pattern::new(&pattern).map_err(|e| format!("Invalid pattern \"{}\": {}", pattern, e))
+}
+
+/// Calls `visit` for every file under `dir`, not descending into excluded
+/// directories, symlinked ones or `node_modules`, whatever the config says.
+fn walk(dir: &Path, exclude: &[Pattern], visit: &mut dyn FnMut(&Path)) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if exclude
+            .iter()
+            .any(|pattern| pattern.matches_path_with(&path, MATCH_OPTIONS))
+        {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() && entry.file_name() != "node_modules" => {
+                walk(&path, exclude, visit)
+            }
+            Ok(file_type) if file_type.is_dir() => {}
+            Ok(_) if path.is_file() => visit(&path),
+            _ => {}
+        }
+    }
+}
+
+/// Strips comments and trailing commas, which tsconfig files allow.
+fn strip_jsonc(contents: &str) -> String {
+    let mut result = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => result.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (']', _) | ('}', _) => {
+                let trimmed = result.trim_end().len();
+                if result[..trimmed].ends_with(',') {
+                    result.truncate(trimmed - 1);
+                }
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+fn has_wildcard(segment: &str) -> bool {
+    segment.contains(['*', '?', '['])
+}
+
+fn is_in_node_modules(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == "node_modules")
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
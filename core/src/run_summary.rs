@@ -0,0 +1,165 @@
+//! The record of a run, persisted under `.cache/runs`.
+
+use super::file_cache::FileCache;
+use super::why::Explanation;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub const RUNS_DIR: &str = "runs";
+
+/// What a run found and how long each of its steps took.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub id: String,
+    pub started_at: u128,
+    pub finished_at: u128,
+    pub duration: u128,
+    pub timings: Vec<Timing>,
+    pub updated: Vec<String>,
+    pub affected: Vec<String>,
+    pub workspaces: Vec<WorkspaceSummary>,
+    pub tasks: Vec<TaskSummary>,
+    pub error: Option<String>,
+
+    #[serde(skip)]
+    start: Option<Instant>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Timing {
+    pub name: String,
+    pub duration: u128,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceStatus {
+    Unchanged,
+    Updated,
+    Affected,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspaceSummary {
+    pub name: String,
+    pub status: WorkspaceStatus,
+    pub changed_files: Vec<String>,
+    /// Set for updated and affected workspaces.
+    #[serde(default)]
+    pub why: Option<Explanation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Succeeded,
+    Failed,
+    /// Terminated after running longer than its timeout.
+    #[serde(rename = "timed_out")]
+    TimedOut,
+    /// A task it depends on didn't succeed.
+    Skipped,
+    /// Killed or never started after another task failed fast.
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskSummary {
+    pub workspace: String,
+    pub task: String,
+    pub status: TaskStatus,
+    pub duration: u128,
+    pub exit_code: Option<i32>,
+    pub cache_hit: bool,
+    /// Full output of the task, under the cache directory.
+    #[serde(default)]
+    pub log_file: Option<String>,
+    /// More than one when the task was retried.
+    #[serde(default)]
+    pub attempts: u32,
+    /// For skipped tasks, the workspace whose task didn't succeed.
+    #[serde(default)]
+    pub blocked_by: Option<String>,
+}
+
+impl RunSummary {
+    pub fn start() -> Self {
+        let started_at = now();
+        Self {
+            id: started_at.to_string(),
+            started_at,
+            finished_at: 0,
+            duration: 0,
+            timings: vec![],
+            updated: vec![],
+            affected: vec![],
+            workspaces: vec![],
+            tasks: vec![],
+            error: None,
+            start: Some(Instant::now()),
+        }
+    }
+
+    pub fn timing(&mut self, name: &str, since: Instant) {
+        self.timings.push(Timing {
+            name: name.to_owned(),
+            duration: since.elapsed().as_millis(),
+        });
+    }
+
+    pub fn set_workspaces(
+        &mut self,
+        names: Vec<String>,
+        changes: &HashMap<String, Vec<String>>,
+        affected: &[String],
+        mut explanations: HashMap<String, Explanation>,
+    ) {
+        let mut workspaces: Vec<WorkspaceSummary> = names
+            .into_iter()
+            .map(|name| {
+                let status = if changes.contains_key(&name) {
+                    WorkspaceStatus::Updated
+                } else if affected.contains(&name) {
+                    WorkspaceStatus::Affected
+                } else {
+                    WorkspaceStatus::Unchanged
+                };
+                let changed_files = changes.get(&name).cloned().unwrap_or_default();
+                let why = explanations.remove(&name);
+                WorkspaceSummary {
+                    name,
+                    status,
+                    changed_files,
+                    why,
+                }
+            })
+            .collect();
+        workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut updated: Vec<String> = changes.keys().cloned().collect();
+        updated.sort();
+
+        self.workspaces = workspaces;
+        self.updated = updated;
+        self.affected = affected.to_vec();
+    }
+
+    pub fn finish(&mut self, cache: &FileCache) -> std::io::Result<()> {
+        self.finished_at = now();
+        self.duration = match self.start {
+            Some(start) => start.elapsed().as_millis(),
+            None => self.finished_at - self.started_at,
+        };
+
+        let serialized = serde_json::to_string_pretty(self).unwrap();
+        cache.write(&format!("{}/{}.json", RUNS_DIR, self.id), &serialized)
+    }
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
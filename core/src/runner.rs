@@ -0,0 +1,347 @@
+//! A full run and the operations sharing its state between calls.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use super::config::Config;
+use super::dependency_graph::DepGraph;
+use super::executor::{execute, ExecutorOptions};
+use super::filter::filter_workspaces;
+use super::graph_query::GraphQuery;
+use super::process_tree;
+use super::profiler::Profiler;
+use super::project::Project;
+use super::prune::{prune, PruneOptions};
+use super::publish::{publish, PublishOptions, PublishResult};
+use super::release::{version, Release};
+use super::resolvers::ResolverRegistry;
+use super::run_summary::{RunSummary, TaskStatus};
+use super::version_policy::{fix_mismatches, VersionPolicy};
+use super::why::{explain, file_changes};
+use super::workspace::Workspace;
+use super::FileCache;
+use std::time::Instant;
+
+/// Options of a single run.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub profile: Option<String>,
+    pub filter: Vec<String>,
+    pub fix: bool,
+    /// Script to run in the affected workspaces once they're found.
+    pub task: Option<String>,
+    pub executor: ExecutorOptions,
+}
+
+/// State shared between runs and the queries made between them.
+pub type SharedCache = Arc<RwLock<FileCache>>;
+pub type SharedProject = Arc<RwLock<Project>>;
+/// Graph of the last run, queries build it on their own before the first one.
+pub type SharedGraph = Arc<RwLock<Option<Arc<DepGraph>>>>;
+
+/// Invalidates the project, validates its graph and finds the affected
+/// workspaces. Runs synchronously, resolvers may wait on other threads
+/// meanwhile, the JS thread for the addon's plugins, so the addon must not
+/// call it there. Fails when the run does, after persisting its summary.
+pub fn run(
+    shared_project: &SharedProject,
+    resolvers: &ResolverRegistry,
+    shared_cache: &SharedCache,
+    shared_graph: &SharedGraph,
+    options: &RunOptions,
+) -> Result<RunSummary, String> {
+    process_tree::reset();
+    let mut summary = RunSummary::start();
+    let profiler = Profiler::new(options.profile.is_some());
+    profiler.name_lane("runner");
+    let cache = shared_cache
+        .read()
+        .expect("[runner:run] Couldn't lock read access to a cache");
+    shared_project
+        .write()
+        .expect("[runner:run] Couldn't lock write access to a project")
+        .refresh_package_json();
+    let project = shared_project
+        .read()
+        .expect("[runner:run] Couldn't lock read access to a project");
+
+    eprint!("Invalidating deps... ");
+    let now = Instant::now();
+    let invalidate_span = profiler.span("invalidate", "project");
+    let (workspaces, updated) = project.invalidate(resolvers, &profiler);
+    drop(invalidate_span);
+    drop(project);
+    eprintln!("[{} ms]", now.elapsed().as_millis());
+    summary.timing("invalidate", now);
+
+    println!("Updated workspaces: {:?}", updated.keys());
+
+    let mut project = shared_project
+        .write()
+        .expect("[runner:run] Couldn't lock read access to a project");
+
+    let previous = std::mem::take(&mut project.workspaces);
+    for ws in workspaces {
+        project.workspaces.insert(ws.name.to_owned(), ws);
+    }
+    let changes = file_changes(&updated, &previous, &project.workspaces, &project.path);
+    drop(previous);
+
+    let config = project.config().clone();
+    if options.fix {
+        fix_versions(&config, &mut project);
+    }
+
+    println!("Building deps graph");
+    let now = Instant::now();
+    let graph_span = profiler.span("build graph", "graph");
+    let dep_graph = Arc::new(DepGraph::new(
+        project.workspaces.values().cloned().collect(),
+    ));
+    drop(graph_span);
+    summary.timing("dep_graph", now);
+
+    println!("Validating deps graph");
+    let now = Instant::now();
+    let validate_span = profiler.span("validate", "graph");
+    let is_valid = dep_graph.validate(&config);
+    drop(validate_span);
+    summary.timing("validate", now);
+
+    let mut affected = vec![];
+    let mut explanations = HashMap::new();
+    let mut interrupted = None;
+    if is_valid {
+        println!("Calculating affected dependencies");
+        let now = Instant::now();
+        let affected_span = profiler.span("affected", "graph");
+        let selected = filter_workspaces(
+            &options.filter,
+            &dep_graph,
+            &project.workspaces,
+            &project.path,
+        );
+        let provenance = dep_graph.trace_affected(updated.keys().cloned().collect());
+        match (
+            selected,
+            dep_graph.top_sort(provenance.keys().cloned().collect()),
+        ) {
+            (Ok(selected), Ok(result)) => {
+                if !options.filter.is_empty() {
+                    println!("Selected workspaces: {:?}", selected);
+                }
+                affected = result
+                    .into_iter()
+                    .filter(|ws| selected.contains(ws))
+                    .collect();
+                explanations = explain(&affected, &provenance, &changes);
+            }
+            (Err(e), _) | (_, Err(e)) => summary.error = Some(e),
+        }
+        drop(affected_span);
+        summary.timing("affected", now);
+        println!("Affected dependencies: {:#?}", affected);
+
+        if let (Some(task), None) = (&options.task, &summary.error) {
+            println!("Running {}", task);
+            let now = Instant::now();
+            let tasks_span = profiler.span("tasks", "executor");
+            let execution = execute(
+                &project,
+                &dep_graph,
+                &affected,
+                task,
+                &cache,
+                &profiler,
+                &options.executor,
+            );
+            summary.tasks = execution.tasks;
+            interrupted = execution.interrupted;
+            drop(tasks_span);
+            summary.timing("tasks", now);
+
+            let failed: Vec<&str> = summary
+                .tasks
+                .iter()
+                .filter(|t| t.status != TaskStatus::Succeeded)
+                .map(|t| t.workspace.as_str())
+                .collect();
+            if interrupted.is_some() {
+                summary.error = Some(format!("{} was interrupted", task));
+            } else if !failed.is_empty() {
+                summary.error = Some(format!("{} didn't succeed in {}", task, failed.join(", ")));
+            }
+        }
+
+        // CACHING
+        // Unless tasks failed, so the next run finds the same workspaces
+        // affected and runs them again.
+        if summary
+            .tasks
+            .iter()
+            .all(|t| t.status == TaskStatus::Succeeded)
+        {
+            let serialized = serde_json::to_string(&project as &Project).unwrap();
+            cache.write("project.json", &serialized).unwrap();
+        }
+    } else {
+        summary.error = Some("Dependency graph is invalid".to_owned());
+    }
+
+    *shared_graph
+        .write()
+        .expect("[runner:run] Couldn't lock write access to a graph") = Some(dep_graph);
+
+    summary.set_workspaces(
+        project.workspaces.keys().cloned().collect(),
+        &updated,
+        &affected,
+        explanations,
+    );
+    if let Err(e) = summary.finish(&cache) {
+        println!("Couldn't write run summary: {}", e);
+    }
+
+    if let Some(profile_path) = &options.profile {
+        match profiler.write(profile_path) {
+            Ok(_) => println!("Profile written to {}", profile_path),
+            Err(e) => println!("Couldn't write profile: {}", e),
+        }
+    }
+
+    // The tasks are terminated and the run recorded, now the interrupt can
+    // do what it would have done. Hosts catching signals themselves do that.
+    if let Some(signal) = interrupted.filter(|_| options.executor.catch_interrupts) {
+        drop(project);
+        drop(cache);
+        process_tree::reraise(signal);
+    }
+
+    match summary.error.clone() {
+        Some(error) => Err(error),
+        None => Ok(summary),
+    }
+}
+
+/// The workspaces a run would find affected, dependencies first, within the
+/// filters. Unlike a run nothing is persisted, so it can be asked any number
+/// of times, and progress goes to stderr, leaving stdout to the result.
+pub fn query_affected(
+    shared_project: &SharedProject,
+    resolvers: &ResolverRegistry,
+    options: &RunOptions,
+) -> Result<Vec<String>, String> {
+    shared_project
+        .write()
+        .expect("[runner:query_affected] Couldn't lock write access to a project")
+        .refresh_package_json();
+    let project = shared_project
+        .read()
+        .expect("[runner:query_affected] Couldn't lock read access to a project");
+
+    let (workspaces, updated) = project.invalidate(resolvers, &Profiler::new(false));
+    eprintln!("Updated workspaces: {:?}", updated.keys());
+    let workspaces: HashMap<String, Workspace> = workspaces
+        .into_iter()
+        .map(|ws| (ws.name.clone(), ws))
+        .collect();
+
+    let dep_graph = DepGraph::new(workspaces.values().cloned().collect());
+    let selected = filter_workspaces(&options.filter, &dep_graph, &workspaces, &project.path)?;
+    let provenance = dep_graph.trace_affected(updated.keys().cloned().collect());
+    let affected = dep_graph.top_sort(provenance.keys().cloned().collect())?;
+    Ok(affected
+        .into_iter()
+        .filter(|ws| selected.contains(ws))
+        .collect())
+}
+
+/// Prunes the project as it is on disk, see [`prune`].
+pub fn prune_project(
+    shared_project: &SharedProject,
+    options: &PruneOptions,
+) -> Result<Vec<String>, String> {
+    let path = shared_project
+        .read()
+        .expect("[runner:prune] Couldn't lock read access to a project")
+        .path
+        .clone();
+    // A cached project can miss workspaces added since the last run.
+    prune(&Project::new(path), options)
+}
+
+/// Applies the pending changesets to the project as it is on disk, see
+/// [`version`].
+pub fn version_project(shared_project: &SharedProject) -> Result<Vec<Release>, String> {
+    let path = shared_project
+        .read()
+        .expect("[runner:version] Couldn't lock read access to a project")
+        .path
+        .clone();
+    version(&Project::new(path))
+}
+
+/// Publishes the project as it is on disk, see [`publish`].
+pub fn publish_project(
+    shared_project: &SharedProject,
+    options: &PublishOptions,
+) -> Result<Vec<PublishResult>, String> {
+    let path = shared_project
+        .read()
+        .expect("[runner:publish] Couldn't lock read access to a project")
+        .path
+        .clone();
+    publish(&Project::new(path), options)
+}
+
+/// Answers the query from the graph of the last run, or from the graph of
+/// the cached project before the first one.
+pub fn query_graph(
+    shared_project: &SharedProject,
+    shared_graph: &SharedGraph,
+    query: &GraphQuery,
+) -> Result<serde_json::Value, String> {
+    let cached = shared_graph
+        .read()
+        .expect("[runner:query] Couldn't lock read access to a graph")
+        .clone();
+    let graph = match cached {
+        Some(graph) => graph,
+        None => {
+            let project = shared_project
+                .read()
+                .expect("[runner:query] Couldn't lock read access to a project");
+            let graph = Arc::new(DepGraph::new(
+                project.workspaces.values().cloned().collect(),
+            ));
+            *shared_graph
+                .write()
+                .expect("[runner:query] Couldn't lock write access to a graph") =
+                Some(Arc::clone(&graph));
+            graph
+        }
+    };
+
+    query.execute(&graph)
+}
+
+fn fix_versions(config: &Config, project: &mut Project) {
+    let mismatches = VersionPolicy::new(&config.version_policy).check(project.workspaces.values());
+    if mismatches.is_empty() {
+        return;
+    }
+
+    println!("Fixing version mismatches");
+    match fix_mismatches(&mismatches, &project.workspaces) {
+        Ok(fixed) => {
+            for name in fixed {
+                if let Some(ws) = project.workspaces.get_mut(&name) {
+                    ws.reload_package_json();
+                }
+            }
+        }
+        Err(e) => println!("Couldn't fix version mismatches: {}", e),
+    }
+}
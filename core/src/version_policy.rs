@@ -0,0 +1,226 @@
+//! Checking and fixing the versions workspaces depend on each other with.
+
+use super::dep_spec::{spec_for_version, split_alias, DepSpec};
+use super::workspace::Workspace;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// `sculpture.versionPolicy` in the root `package.json`:
+///
+/// ```json
+/// {
+///   "canonical": { "react": "^17.0.2" },
+///   "exceptions": { "legacy-app": ["react"] }
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VersionPolicyConfig {
+    /// Dependency name -> the spec every workspace has to use.
+    #[serde(default)]
+    pub canonical: HashMap<String, String>,
+
+    /// Workspace name -> dependencies it's allowed to keep at any version.
+    #[serde(default)]
+    pub exceptions: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub dependency: String,
+    pub expected_version: Version,
+    pub expected_spec: String,
+    pub pinned: bool,
+    pub usages: Vec<Usage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Usage {
+    pub workspace: String,
+    pub spec: String,
+}
+
+pub struct VersionPolicy<'a> {
+    config: &'a VersionPolicyConfig,
+}
+
+impl<'a> VersionPolicy<'a> {
+    pub fn new(config: &'a VersionPolicyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Collects every external dependency whose specs disagree, sorted by
+    /// dependency name. The expected version is the configured canonical
+    /// spec, or the highest version requested by any workspace. Dependencies
+    /// on local workspaces are left to DepGraph, which links them only when
+    /// the range matches.
+    pub fn check<'w>(&self, workspaces: impl Iterator<Item = &'w Workspace>) -> Vec<Mismatch> {
+        let mut local_names: HashSet<String> = HashSet::new();
+        let mut usages: BTreeMap<String, Vec<(Usage, DepSpec)>> = BTreeMap::new();
+
+        for ws in workspaces {
+            local_names.insert(ws.name.clone());
+
+            for (dep, spec) in &ws.package_json.dependencies {
+                if self.is_exception(&ws.name, dep) {
+                    continue;
+                }
+                // Unparsable specs are reported by DepGraph::validate.
+                if let Ok(parsed) = DepSpec::parse(spec) {
+                    let usage = Usage {
+                        workspace: ws.name.clone(),
+                        spec: spec.clone(),
+                    };
+                    usages
+                        .entry(parsed.package_name(dep).to_owned())
+                        .or_default()
+                        .push((usage, parsed));
+                }
+            }
+        }
+
+        let mut mismatches = vec![];
+        for (dependency, mut dep_usages) in usages {
+            if local_names.contains(&dependency) {
+                continue;
+            }
+            dep_usages.sort_by(|a, b| a.0.workspace.cmp(&b.0.workspace));
+
+            let (expected_version, expected_spec) =
+                match self.expected_version(&dependency, &dep_usages) {
+                    Some(expected) => expected,
+                    None => continue,
+                };
+            let pinned = self.config.canonical.contains_key(&dependency);
+
+            let offending: Vec<Usage> = dep_usages
+                .into_iter()
+                .filter(|(usage, spec)| match spec.matches(&expected_version) {
+                    Some(false) => true,
+                    Some(true) => pinned && usage.spec != expected_spec,
+                    None => false,
+                })
+                .map(|(usage, _)| usage)
+                .collect();
+
+            if !offending.is_empty() {
+                mismatches.push(Mismatch {
+                    dependency,
+                    expected_version,
+                    expected_spec,
+                    pinned,
+                    usages: offending,
+                });
+            }
+        }
+
+        mismatches
+    }
+
+    fn expected_version(
+        &self,
+        dependency: &str,
+        usages: &[(Usage, DepSpec)],
+    ) -> Option<(Version, String)> {
+        if let Some(canonical) = self.config.canonical.get(dependency) {
+            return match DepSpec::parse(canonical) {
+                Ok(DepSpec::Range(range)) => range.min_version().map(|v| (v, canonical.clone())),
+                _ => {
+                    println!(
+                        "Canonical version \"{}\" for \"{}\" is not a version range",
+                        canonical, dependency
+                    );
+                    None
+                }
+            };
+        }
+
+        usages
+            .iter()
+            .filter_map(|(usage, spec)| match spec {
+                DepSpec::Range(range) => range.min_version().map(|v| (v, usage.spec.clone())),
+                _ => None,
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+    }
+
+    fn is_exception(&self, workspace: &str, dep: &str) -> bool {
+        self.config
+            .exceptions
+            .get(workspace)
+            .is_some_and(|deps| deps.iter().any(|d| d == dep))
+    }
+}
+
+impl Mismatch {
+    pub fn print(&self) {
+        println!(
+            "Package \"{}\" must be used at a single version, expected \"{}\":",
+            self.dependency, self.expected_spec
+        );
+        for usage in &self.usages {
+            println!(
+                "  {} depends on \"{}@{}\"",
+                usage.workspace, self.dependency, usage.spec
+            );
+        }
+    }
+
+    /// Spec that replaces an offending one. Pinned dependencies get the
+    /// canonical spec as is, others keep the `^`/`~` style of the original.
+    pub fn fixed_spec(&self, old_spec: &str) -> String {
+        if let Some((name, range)) = old_spec.strip_prefix("npm:").and_then(split_alias) {
+            return format!("npm:{}@{}", name, self.fixed_spec(range));
+        }
+
+        if self.pinned {
+            return self.expected_spec.clone();
+        }
+
+        spec_for_version(old_spec, &self.expected_version)
+    }
+}
+
+/// Rewrites the offending ranges in each workspace's `package.json`. Returns
+/// the names of workspaces whose manifests changed.
+pub fn fix_mismatches(
+    mismatches: &[Mismatch],
+    workspaces: &HashMap<String, Workspace>,
+) -> Result<Vec<String>, String> {
+    let mut fixed = vec![];
+
+    for mismatch in mismatches {
+        for usage in &mismatch.usages {
+            let ws = match workspaces.get(&usage.workspace) {
+                Some(ws) => ws,
+                None => continue,
+            };
+            let dep_key = ws
+                .package_json
+                .dependencies
+                .iter()
+                .find(|(key, spec)| {
+                    *spec == &usage.spec
+                        && DepSpec::parse(spec)
+                            .map(|s| s.package_name(key) == mismatch.dependency)
+                            .unwrap_or(false)
+                })
+                .map(|(key, _)| key.clone());
+
+            if let Some(dep_key) = dep_key {
+                let new_spec = mismatch.fixed_spec(&usage.spec);
+                ws.package_json
+                    .set_dependency_spec(&dep_key, &usage.spec, &new_spec)?;
+                println!(
+                    "Fixed {}: \"{}\" {} -> {}",
+                    usage.workspace, dep_key, usage.spec, new_spec
+                );
+                if !fixed.contains(&usage.workspace) {
+                    fixed.push(usage.workspace.clone());
+                }
+            }
+        }
+    }
+
+    Ok(fixed)
+}
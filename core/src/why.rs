@@ -0,0 +1,100 @@
+//! Explanations of why a workspace is part of a run.
+
+use super::dependency_graph::Provenance;
+use super::workspace::Workspace;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Why a workspace is part of the run: the chain of dependencies leading
+/// to an updated workspace and the files that changed there.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Explanation {
+    /// From the explained workspace down to the updated one, both included.
+    pub chain: Vec<String>,
+    pub changes: Vec<FileChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileChange {
+    /// Relative to the project root.
+    pub path: String,
+    /// `None` when the file was added.
+    pub old_hash: Option<String>,
+    /// `None` when the file was removed.
+    pub new_hash: Option<String>,
+}
+
+/// Explanations of the given affected workspaces, keyed by name.
+pub fn explain(
+    affected: &[String],
+    provenance: &Provenance,
+    changes: &HashMap<String, Vec<FileChange>>,
+) -> HashMap<String, Explanation> {
+    affected
+        .iter()
+        .map(|name| {
+            let chain = chain(provenance, name);
+            let changes = chain
+                .last()
+                .and_then(|updated| changes.get(updated))
+                .cloned()
+                .unwrap_or_default();
+            (name.to_owned(), Explanation { chain, changes })
+        })
+        .collect()
+}
+
+/// Hashes before and after the run of every changed file. Lockfile changes
+/// aren't workspace files, they are compared by the workspace lockfile hash.
+pub fn file_changes(
+    updated: &HashMap<String, Vec<String>>,
+    before: &HashMap<String, Workspace>,
+    after: &HashMap<String, Workspace>,
+    root: &str,
+) -> HashMap<String, Vec<FileChange>> {
+    updated
+        .iter()
+        .map(|(name, paths)| {
+            let old_ws = before.get(name);
+            let new_ws = after.get(name);
+            let changes = paths
+                .iter()
+                .map(|path| {
+                    let old_file = old_ws.and_then(|ws| ws.files.get(path));
+                    let new_file = new_ws.and_then(|ws| ws.files.get(path));
+                    let (old_hash, new_hash) = if old_file.is_none() && new_file.is_none() {
+                        (
+                            old_ws.and_then(|ws| ws.lockfile_hash.clone()),
+                            new_ws.and_then(|ws| ws.lockfile_hash.clone()),
+                        )
+                    } else {
+                        (
+                            old_file.map(|file| file.hash.clone()),
+                            new_file.map(|file| file.hash.clone()),
+                        )
+                    };
+                    FileChange {
+                        path: Path::new(path)
+                            .strip_prefix(root)
+                            .map(|p| p.to_string_lossy().into_owned())
+                            .unwrap_or_else(|_| path.to_owned()),
+                        old_hash,
+                        new_hash,
+                    }
+                })
+                .collect();
+            (name.to_owned(), changes)
+        })
+        .collect()
+}
+
+fn chain(provenance: &Provenance, name: &str) -> Vec<String> {
+    let mut chain = vec![name.to_owned()];
+    let mut cur = name;
+    while let Some(Some(dependency)) = provenance.get(cur) {
+        chain.push(dependency.to_owned());
+        cur = dependency;
+    }
+    chain
+}
@@ -0,0 +1,139 @@
+//! A single package of the project and its input files.
+
+use super::file::File;
+use super::git_index::GitIndex;
+use super::hasher::Hasher;
+use super::package_json::PackageJson;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+
+/// A package of the project, with the input files of the last run.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Workspace {
+    pub name: String,
+    pub path: String,
+    pub files: WorkspaceFiles,
+    pub package_json: PackageJson,
+
+    /// Fingerprint of the external packages this workspace resolves through
+    /// the project lockfile.
+    #[serde(default)]
+    pub lockfile_hash: Option<String>,
+}
+
+pub type WorkspaceFiles = HashMap<String, File>;
+
+impl Workspace {
+    /// Reads the workspace from its `package.json` at `path`.
+    pub fn new(path: String) -> Self {
+        let pkg_json = PackageJson::new(&path);
+        Self {
+            name: pkg_json.name.clone(),
+            path: Path::new(&path)
+                .parent()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_owned(),
+            files: HashMap::new(),
+            package_json: pkg_json,
+            lockfile_hash: None,
+        }
+    }
+
+    /// Path relative to the project root, `""` for the root itself.
+    pub fn relative_path(&self, root: &str) -> String {
+        Path::new(&self.path)
+            .strip_prefix(root)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the input files, re-reading `package.json` along with them.
+    pub fn update(&mut self, files: WorkspaceFiles) {
+        self.files = files;
+        self.reload_package_json();
+    }
+
+    pub fn reload_package_json(&mut self) {
+        self.package_json = PackageJson::new(&self.package_json.path);
+    }
+
+    /// Hashes the resolved inputs and `package.json` on the pool, resolving
+    /// with the paths that changed, were added or removed, and the new files.
+    /// Files new to the workspace take their hash from the git index when
+    /// given one. Files that can't be read count as removed.
+    pub fn invalidate(
+        &self,
+        mut resolved_inputs: Vec<String>,
+        git_index: Option<&GitIndex>,
+        hasher: &Hasher,
+    ) -> impl Future<Output = (Vec<String>, WorkspaceFiles)> {
+        resolved_inputs.push(format!("{}/package.json", self.path));
+        resolved_inputs.sort();
+        resolved_inputs.dedup();
+
+        let jobs: Vec<HashJob> = resolved_inputs
+            .into_iter()
+            .map(|path| match self.files.get(&path) {
+                Some(file) => HashJob::Invalidate(file.clone()),
+                None => match git_index.and_then(|index| index.object_id(&path)) {
+                    Some(id) => HashJob::Seed(path, id.to_owned()),
+                    None => HashJob::Hash(path),
+                },
+            })
+            .collect();
+        let old_paths: Vec<String> = self.files.keys().cloned().collect();
+        let hashed = hasher.map(jobs, HashJob::run);
+
+        async move {
+            let mut new_files: WorkspaceFiles = HashMap::new();
+            let mut changed_files: Vec<String> = vec![];
+
+            for result in hashed.await {
+                match result {
+                    Ok((dirty, new_file)) => {
+                        if dirty {
+                            changed_files.push(new_file.path.clone());
+                        }
+                        new_files.insert(new_file.path.clone(), new_file);
+                    }
+                    Err((path, e)) => eprintln!("Couldn't hash {}: {}", path, e),
+                }
+            }
+
+            for file_path in old_paths {
+                if !new_files.contains_key(&file_path) {
+                    changed_files.push(file_path);
+                }
+            }
+            changed_files.sort();
+
+            (changed_files, new_files)
+        }
+    }
+}
+
+/// Work for a single input file, done on the hashing pool.
+enum HashJob {
+    Invalidate(File),
+    Seed(String, String),
+    Hash(String),
+}
+
+impl HashJob {
+    /// Whether the file changed and its new state.
+    fn run(self) -> Result<(bool, File), (String, std::io::Error)> {
+        match self {
+            HashJob::Invalidate(file) => file.invalidate().map_err(|e| (file.path, e)),
+            HashJob::Seed(path, id) => File::with_hash(path.clone(), id)
+                .map(|file| (true, file))
+                .map_err(|e| (path, e)),
+            HashJob::Hash(path) => File::new(path.clone())
+                .map(|file| (true, file))
+                .map_err(|e| (path, e)),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+/**
+ * Declarations for the native addon, kept in sync with src/lib.rs and the
+ * serialized structs in src/run_summary.rs.
+ */
+
+export type TaskSummary = {
+  workspace: string;
+  task: string;
+  /**
+   * Skipped when a task it depends on didn't succeed, cancelled when killed
+   * or never started after another one failed
+   */
+  status: "succeeded" | "failed" | "timed_out" | "skipped" | "cancelled";
+  duration: number;
+  exit_code: number | null;
+  cache_hit: boolean;
+  /** Full output of the task, under the cache directory */
+  log_file: string | null;
+  /** More than 1 when the task was retried, 0 when it didn't run */
+  attempts: number;
+  /** For skipped tasks, the workspace whose task didn't succeed */
+  blocked_by: string | null;
+};
+
+export type FileChange = {
+  /** Relative to the project root */
+  path: string;
+  /** null when the file was added */
+  old_hash: string | null;
+  /** null when the file was removed */
+  new_hash: string | null;
+};
+
+export type Explanation = {
+  /** From the explained workspace down to the updated one, both included */
+  chain: Array<string>;
+  /** Changes in the updated workspace */
+  changes: Array<FileChange>;
+};
+
+export type WorkspaceSummary = {
+  name: string;
+  status: "unchanged" | "updated" | "affected";
+  changed_files: Array<string>;
+  /** Set for updated and affected workspaces */
+  why: Explanation | null;
+};
+
+export type RunSummary = {
+  id: string;
+  started_at: number;
+  finished_at: number;
+  duration: number;
+  timings: Array<{ name: string; duration: number }>;
+  updated: Array<string>;
+  affected: Array<string>;
+  workspaces: Array<WorkspaceSummary>;
+  tasks: Array<TaskSummary>;
+  error: string | null;
+};
+
+export type OrchestratorParams = {
+  cwd: string;
+  /** Write a Chrome Trace Event profile of each run to this file */
+  profile?: string;
+  /** pnpm-style filter expressions */
+  filter?: string | Array<string>;
+  /** Rewrite dependency ranges that break the version policy */
+  fix?: boolean;
+  /** Script to run in the affected workspaces, dependencies first */
+  task?: string;
+  /**
+   * How task output is printed: lines prefixed with [workspace:task] as
+   * they come, all of a task's output when it's done, or only the output
+   * of failed tasks. "stream" by default
+   */
+  output?: "stream" | "grouped" | "errors-only";
+  /** Tasks running at once, the number of CPUs by default */
+  concurrency?: number;
+  /**
+   * Only skip tasks depending on a failed one. By default a failure kills
+   * the running tasks and cancels the rest
+   */
+  continueOnError?: boolean;
+  /**
+   * Seconds after which tasks without a timeout in the "timeouts" config
+   * are terminated, along with the processes they started
+   */
+  timeout?: number;
+  /**
+   * Resolves inputs of a workspace for the "js" resolver, which is only
+   * registered when this is passed. Complete with `onCompleteJsTask`.
+   */
+  onResolveInputs?: (err: unknown, id: string, wsPath: string) => void;
+};
+
+export type PruneOptions = {
+  workspace: string;
+  /** Relative to cwd, "out" by default */
+  outDir?: string;
+  /** Split the output into "json" and "full" directories */
+  docker?: boolean;
+};
+
+export type Bump = "patch" | "minor" | "major";
+
+export type Change = {
+  /** Id of the changeset */
+  id: string;
+  bump: Bump;
+  summary: string;
+};
+
+export type DependencyUpdate = {
+  /** Key in `dependencies` */
+  name: string;
+  /** Name of the released workspace, differs from `name` for aliases */
+  package: string;
+  /** Its new version */
+  version: string;
+  old_spec: string;
+  new_spec: string;
+};
+
+export type Release = {
+  name: string;
+  old_version: string;
+  new_version: string;
+  bump: Bump;
+  /** Changesets releasing it, empty when only released for dependencies */
+  changes: Array<Change>;
+  /** Ranges on released workspaces that the new versions would break */
+  dependency_updates: Array<DependencyUpdate>;
+};
+
+export type PublishOptions = {
+  /** Registry to check and publish to, npm's configuration by default */
+  registry?: string;
+};
+
+export type PublishResult = {
+  name: string;
+  version: string;
+  status: "published" | "already_published" | "failed" | "skipped";
+  /** Set for failed workspaces, and skipped ones whose dependency failed */
+  error: string | null;
+};
+
+export type GraphQueryOptions = {
+  /** Include indirect dependencies or dependents, false by default */
+  transitive?: boolean;
+};
+
+export declare class Orchestrator {
+  constructor(params: OrchestratorParams);
+
+  /**
+   * Resolves with the run summary, rejects when the run fails. Either way
+   * the summary is persisted in .cache/runs.
+   */
+  run(): Promise<RunSummary>;
+
+  /** Resolves with names of the workspaces written to the output */
+  prune(options: PruneOptions): Promise<Array<string>>;
+
+  /**
+   * Applies the changesets in .changeset: bumps versions, rewrites ranges
+   * of dependents the new versions would break, prepends CHANGELOG.md
+   * entries and removes the changesets.
+   * Resolves with the releases, dependencies first.
+   */
+  version(): Promise<Array<Release>>;
+
+  /**
+   * Publishes non-private workspaces whose version isn't in the registry,
+   * dependencies first, with `workspace:` specs replaced by versions in the
+   * packed manifests. Dependents of a workspace that failed are skipped.
+   */
+  publish(options?: PublishOptions): Promise<Array<PublishResult>>;
+
+  /**
+   * Graph queries answer from the graph of the last run, or from the cached
+   * project when there was none. They reject on unknown workspaces.
+   */
+  dependencies(
+    name: string,
+    options?: GraphQueryOptions
+  ): Promise<Array<string>>;
+
+  dependents(
+    name: string,
+    options?: GraphQueryOptions
+  ): Promise<Array<string>>;
+
+  /** Every workspace, dependencies first. Rejects on cycles */
+  topologicalOrder(): Promise<Array<string>>;
+
+  /** Shortest dependency chain from one workspace to another, both included */
+  path(from: string, to: string): Promise<Array<string> | null>;
+
+  /** Workspaces affected by changes in the given ones, dependencies first */
+  affected(names: string | Array<string>): Promise<Array<string>>;
+
+  /**
+   * Terminates the tasks of the current run, which then rejects. Tasks run in
+   * process groups of their own, so signals have to be forwarded this way
+   */
+  cancel(): void;
+
+  onCompleteJsTask(id: string, data: string): void;
+}
@@ -0,0 +1,55 @@
+use super::js_task::{JsTask, JsTasksMap};
+use futures::future::FutureExt;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use sculpture_core::resolvers::{InputResolver, ResolveFuture};
+
+/// Adapter for the JS plugins, which get a task id and a workspace path and
+/// complete the task with a JSON list of files.
+pub struct JsResolver {
+    on_resolve: ThreadsafeFunction<Vec<String>>,
+    async_tasks: JsTasksMap,
+}
+
+impl JsResolver {
+    pub fn new(on_resolve: ThreadsafeFunction<Vec<String>>, async_tasks: JsTasksMap) -> Self {
+        Self {
+            on_resolve,
+            async_tasks,
+        }
+    }
+}
+
+impl InputResolver for JsResolver {
+    fn name(&self) -> &str {
+        "js"
+    }
+
+    /// Only registered when there are plugins, which decide for themselves.
+    fn applies(&self, _ws_path: &str) -> bool {
+        true
+    }
+
+    fn resolve(&self, ws_path: &str) -> ResolveFuture {
+        let task = JsTask::new(format!("{}:{}", ws_path, "resolve_inputs"));
+        let state = task.state.clone();
+        self.async_tasks
+            .write()
+            .expect("RwLock poisoned")
+            .insert(task.id.clone(), task.state.clone());
+        self.on_resolve.call(
+            Ok(vec![task.id.clone(), ws_path.to_owned()]),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+
+        async move {
+            task.await;
+            let state = state.lock().unwrap();
+            let data = state
+                .data
+                .as_ref()
+                .ok_or_else(|| "JS plugins returned no data".to_owned())?;
+            serde_json::from_str(data).map_err(|e| e.to_string())
+        }
+        .boxed()
+    }
+}
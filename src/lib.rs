@@ -1,38 +1,70 @@
 #![deny(clippy::all)]
 
-mod dependency_graph;
-mod file;
-mod file_cache;
+mod js_resolver;
 mod js_task;
-mod package_json;
-mod project;
 mod runner;
-mod workspace;
 
-use file_cache::FileCache;
 use napi::{
   threadsafe_function::{ThreadSafeCallContext, ThreadsafeFunction},
-  CallContext, Env, JsFunction, JsObject, JsString, JsUndefined, Property, Result,
+  CallContext, Env, Error, JsBoolean, JsFunction, JsNumber, JsObject, JsString, JsUndefined,
+  JsUnknown, Property, Result, Task, ValueType,
 };
-use runner::{on_complete_js_task, run, Runner};
+use runner::{on_complete_js_task, Runner};
+use sculpture_core::executor::{ExecutorOptions, FailurePolicy, OutputMode};
+use sculpture_core::process_tree;
+use sculpture_core::runner::{SharedCache, SharedGraph, SharedProject};
+use sculpture_core::{
+  prune_project, publish_project, query_graph, run, version_project, FileCache, GraphQuery,
+  PruneOptions, PublishOptions, PublishResult, Release, ResolverRegistry, RunOptions, RunSummary,
+};
+use std::convert::TryFrom;
+use std::sync::Arc;
 
 #[macro_use]
 extern crate napi_derive;
 
+/// Resolves with the run summary, rejects with the error the run failed with.
+struct RunTask {
+  project: SharedProject,
+  resolvers: ResolverRegistry,
+  cache: SharedCache,
+  graph: SharedGraph,
+  options: RunOptions,
+}
+
+impl Task for RunTask {
+  type Output = RunSummary;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    run(
+      &self.project,
+      &self.resolvers,
+      &self.cache,
+      &self.graph,
+      &self.options,
+    )
+    .map_err(Error::from_reason)
+  }
+
+  fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.to_js_value(&output)
+  }
+}
+
 #[js_function(1)]
-fn run_js_interface(ctx: CallContext) -> Result<JsUndefined> {
+fn run_js_interface(ctx: CallContext) -> Result<JsObject> {
   let this: JsObject = ctx.this_unchecked();
   let runner: &mut Runner = ctx.env.unwrap(&this)?;
 
-  run(
-    &runner.project,
-    &runner.async_tasks,
-    &runner.on_finish,
-    &runner.on_resolve,
-    &runner.cache,
-  );
-
-  ctx.env.get_undefined()
+  let task = RunTask {
+    project: Arc::clone(&runner.project),
+    resolvers: runner.resolvers.clone(),
+    cache: Arc::clone(&runner.cache),
+    graph: Arc::clone(&runner.graph),
+    options: runner.options.clone(),
+  };
+  ctx.env.spawn(task).map(|promise| promise.promise_object())
 }
 
 #[js_function(2)]
@@ -47,6 +79,196 @@ fn on_complete_js_task_js_interface(ctx: CallContext) -> Result<JsUndefined> {
   ctx.env.get_undefined()
 }
 
+/// Resolves with names of the pruned workspaces.
+struct PruneTask {
+  project: SharedProject,
+  options: PruneOptions,
+}
+
+impl Task for PruneTask {
+  type Output = Vec<String>;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    prune_project(&self.project, &self.options).map_err(Error::from_reason)
+  }
+
+  fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.to_js_value(&output)
+  }
+}
+
+#[js_function(1)]
+fn prune_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let this: JsObject = ctx.this_unchecked();
+  let params = ctx.get::<JsObject>(0)?;
+  let options = PruneOptions {
+    workspace: params
+      .get_named_property::<JsString>("workspace")?
+      .into_utf8()?
+      .as_str()?
+      .to_owned(),
+    out_dir: get_optional_string(&params, "outDir")?.unwrap_or_else(|| "out".to_owned()),
+    docker: get_optional_bool(&params, "docker")?,
+  };
+  let runner: &mut Runner = ctx.env.unwrap(&this)?;
+
+  let task = PruneTask {
+    project: Arc::clone(&runner.project),
+    options,
+  };
+  ctx.env.spawn(task).map(|promise| promise.promise_object())
+}
+
+/// Resolves with the releases of the applied changesets.
+struct VersionTask {
+  project: SharedProject,
+}
+
+impl Task for VersionTask {
+  type Output = Vec<Release>;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    version_project(&self.project).map_err(Error::from_reason)
+  }
+
+  fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.to_js_value(&output)
+  }
+}
+
+#[js_function(1)]
+fn version_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let this: JsObject = ctx.this_unchecked();
+  let runner: &mut Runner = ctx.env.unwrap(&this)?;
+
+  let task = VersionTask {
+    project: Arc::clone(&runner.project),
+  };
+  ctx.env.spawn(task).map(|promise| promise.promise_object())
+}
+
+/// Resolves with a result per published workspace.
+struct PublishTask {
+  project: SharedProject,
+  options: PublishOptions,
+}
+
+impl Task for PublishTask {
+  type Output = Vec<PublishResult>;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    publish_project(&self.project, &self.options).map_err(Error::from_reason)
+  }
+
+  fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.to_js_value(&output)
+  }
+}
+
+#[js_function(1)]
+fn publish_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let this: JsObject = ctx.this_unchecked();
+  let params = ctx.get::<JsUnknown>(0)?;
+  let options = match params.get_type()? {
+    ValueType::Object => PublishOptions {
+      registry: get_optional_string(&JsObject::try_from(params)?, "registry")?,
+    },
+    _ => PublishOptions::default(),
+  };
+  let runner: &mut Runner = ctx.env.unwrap(&this)?;
+
+  let task = PublishTask {
+    project: Arc::clone(&runner.project),
+    options,
+  };
+  ctx.env.spawn(task).map(|promise| promise.promise_object())
+}
+
+/// Resolves with the answer to a dependency graph query, rejects on unknown
+/// workspaces and cycles.
+struct GraphQueryTask {
+  project: SharedProject,
+  graph: SharedGraph,
+  query: GraphQuery,
+}
+
+impl Task for GraphQueryTask {
+  type Output = serde_json::Value;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    query_graph(&self.project, &self.graph, &self.query).map_err(Error::from_reason)
+  }
+
+  fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.to_js_value(&output)
+  }
+}
+
+fn spawn_graph_query(ctx: &CallContext, query: GraphQuery) -> Result<JsObject> {
+  let this: JsObject = ctx.this_unchecked();
+  let runner: &mut Runner = ctx.env.unwrap(&this)?;
+
+  let task = GraphQueryTask {
+    project: Arc::clone(&runner.project),
+    graph: Arc::clone(&runner.graph),
+    query,
+  };
+  ctx.env.spawn(task).map(|promise| promise.promise_object())
+}
+
+/// `transitive` of the optional options object.
+fn get_transitive(ctx: &CallContext, index: usize) -> Result<bool> {
+  let options = ctx.get::<JsUnknown>(index)?;
+  if options.get_type()? != ValueType::Object {
+    return Ok(false);
+  }
+
+  get_optional_bool(&JsObject::try_from(options)?, "transitive")
+}
+
+#[js_function(2)]
+fn dependencies_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let name = ctx.get::<JsString>(0)?.into_utf8()?.as_str()?.to_owned();
+  let transitive = get_transitive(&ctx, 1)?;
+  spawn_graph_query(&ctx, GraphQuery::Dependencies { name, transitive })
+}
+
+#[js_function(2)]
+fn dependents_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let name = ctx.get::<JsString>(0)?.into_utf8()?.as_str()?.to_owned();
+  let transitive = get_transitive(&ctx, 1)?;
+  spawn_graph_query(&ctx, GraphQuery::Dependents { name, transitive })
+}
+
+#[js_function(1)]
+fn topological_order_js_interface(ctx: CallContext) -> Result<JsObject> {
+  spawn_graph_query(&ctx, GraphQuery::TopologicalOrder)
+}
+
+#[js_function(2)]
+fn path_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let from = ctx.get::<JsString>(0)?.into_utf8()?.as_str()?.to_owned();
+  let to = ctx.get::<JsString>(1)?.into_utf8()?.as_str()?.to_owned();
+  spawn_graph_query(&ctx, GraphQuery::Path { from, to })
+}
+
+#[js_function(1)]
+fn affected_js_interface(ctx: CallContext) -> Result<JsObject> {
+  let names = to_string_list(ctx.get::<JsUnknown>(0)?)?;
+  spawn_graph_query(&ctx, GraphQuery::Affected(names))
+}
+
+/// Terminates the tasks of the current run, for the signal handlers of JS.
+#[js_function(1)]
+fn cancel_js_interface(ctx: CallContext) -> Result<JsUndefined> {
+  process_tree::interrupt();
+  ctx.env.get_undefined()
+}
+
 #[js_function(1)]
 fn runner_class_constructor(ctx: CallContext) -> Result<JsUndefined> {
   let mut this: JsObject = ctx.this_unchecked();
@@ -58,51 +280,110 @@ fn runner_class_constructor(ctx: CallContext) -> Result<JsUndefined> {
     .as_str()?
     .to_owned();
 
-  let on_finish = create_on_finish(&ctx, params.get_named_property::<JsFunction>("onFinish")?)?;
-  let on_resolve = create_on_resolve(
-    &ctx,
-    params.get_named_property::<JsFunction>("onResolveInputs")?,
-  )?;
+  // Only passed when there are JS plugins.
+  let on_resolve = match params.get_named_property::<JsUnknown>("onResolveInputs")? {
+    value if value.get_type()? == ValueType::Function => {
+      Some(create_on_resolve(&ctx, JsFunction::try_from(value)?)?)
+    }
+    _ => None,
+  };
+  let output = match get_optional_string(&params, "output")? {
+    Some(mode) => OutputMode::parse(&mode).map_err(Error::from_reason)?,
+    None => OutputMode::default(),
+  };
+  let options = RunOptions {
+    profile: get_optional_string(&params, "profile")?,
+    filter: get_string_list(&params, "filter")?,
+    fix: get_optional_bool(&params, "fix")?,
+    task: get_optional_string(&params, "task")?,
+    executor: ExecutorOptions {
+      output,
+      concurrency: get_optional_number(&params, "concurrency")?.map(|n| n as usize),
+      policy: if get_optional_bool(&params, "continueOnError")? {
+        FailurePolicy::Continue
+      } else {
+        FailurePolicy::FailFast
+      },
+      timeout: get_optional_number(&params, "timeout")?.map(u64::from),
+      catch_interrupts: false,
+    },
+  };
   let cache_path = format!("{}{}{}", cwd, std::path::MAIN_SEPARATOR, ".cache");
   let cache = FileCache::new(cache_path);
 
-  let runner = Runner::new(cwd, cache, on_finish, on_resolve);
+  let runner = Runner::new(cwd, cache, on_resolve, options);
   ctx.env.wrap(&mut this, runner)?;
   ctx.env.get_undefined()
 }
 
-fn create_on_finish(
-  ctx: &CallContext,
-  on_finish_cb: JsFunction,
-) -> Result<ThreadsafeFunction<Vec<bool>>> {
-  Ok(ctx.env.create_threadsafe_function(
-    &on_finish_cb,
-    0,
-    |ctx: ThreadSafeCallContext<Vec<bool>>| {
-      ctx
-        .value
-        .iter()
-        .map(|_| ctx.env.get_undefined())
-        .collect::<Result<Vec<JsUndefined>>>()
-    },
-  )?)
+fn get_optional_string(params: &JsObject, name: &str) -> Result<Option<String>> {
+  let value = params.get_named_property::<JsUnknown>(name)?;
+  if value.get_type()? != ValueType::String {
+    return Ok(None);
+  }
+
+  Ok(Some(
+    JsString::try_from(value)?.into_utf8()?.as_str()?.to_owned(),
+  ))
+}
+
+fn get_optional_number(params: &JsObject, name: &str) -> Result<Option<u32>> {
+  let value = params.get_named_property::<JsUnknown>(name)?;
+  if value.get_type()? != ValueType::Number {
+    return Ok(None);
+  }
+
+  Ok(Some(JsNumber::try_from(value)?.get_uint32()?))
+}
+
+fn get_optional_bool(params: &JsObject, name: &str) -> Result<bool> {
+  let value = params.get_named_property::<JsUnknown>(name)?;
+  if value.get_type()? != ValueType::Boolean {
+    return Ok(false);
+  }
+
+  JsBoolean::try_from(value)?.get_value()
+}
+
+fn get_string_list(params: &JsObject, name: &str) -> Result<Vec<String>> {
+  to_string_list(params.get_named_property::<JsUnknown>(name)?)
+}
+
+/// A single string or an array of strings.
+fn to_string_list(value: JsUnknown) -> Result<Vec<String>> {
+  match value.get_type()? {
+    ValueType::String => Ok(vec![JsString::try_from(value)?
+      .into_utf8()?
+      .as_str()?
+      .to_owned()]),
+    ValueType::Object => {
+      let list = JsObject::try_from(value)?;
+      let mut result = vec![];
+      for i in 0..list.get_array_length()? {
+        let item = list.get_element::<JsString>(i)?;
+        result.push(item.into_utf8()?.as_str()?.to_owned());
+      }
+      Ok(result)
+    }
+    _ => Ok(vec![]),
+  }
 }
 
 fn create_on_resolve(
   ctx: &CallContext,
   on_finish_cb: JsFunction,
 ) -> Result<ThreadsafeFunction<Vec<String>>> {
-  Ok(ctx.env.create_threadsafe_function(
+  ctx.env.create_threadsafe_function(
     &on_finish_cb,
     0,
     |ctx: ThreadSafeCallContext<Vec<String>>| {
       ctx
         .value
         .iter()
-        .map(|v| ctx.env.create_string(&*v))
+        .map(|v| ctx.env.create_string(v))
         .collect::<Result<Vec<JsString>>>()
     },
-  )?)
+  )
 }
 
 #[module_exports]
@@ -113,6 +394,15 @@ fn init(mut exports: JsObject, env: Env) -> Result<()> {
     &[
       Property::new(&env, "run")?.with_method(run_js_interface),
       Property::new(&env, "onCompleteJsTask")?.with_method(on_complete_js_task_js_interface),
+      Property::new(&env, "prune")?.with_method(prune_js_interface),
+      Property::new(&env, "version")?.with_method(version_js_interface),
+      Property::new(&env, "publish")?.with_method(publish_js_interface),
+      Property::new(&env, "dependencies")?.with_method(dependencies_js_interface),
+      Property::new(&env, "dependents")?.with_method(dependents_js_interface),
+      Property::new(&env, "topologicalOrder")?.with_method(topological_order_js_interface),
+      Property::new(&env, "path")?.with_method(path_js_interface),
+      Property::new(&env, "affected")?.with_method(affected_js_interface),
+      Property::new(&env, "cancel")?.with_method(cancel_js_interface),
     ],
   )?;
   exports.set_named_property("Orchestrator", runner)?;
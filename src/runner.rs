@@ -1,116 +1,63 @@
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
-    thread,
 };
 
-use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::threadsafe_function::ThreadsafeFunction;
+use sculpture_core::runner::{SharedCache, SharedGraph, SharedProject};
+use sculpture_core::{FileCache, Project, ResolverRegistry, RunOptions};
 
-use super::dependency_graph::DepGraph;
+use super::js_resolver::JsResolver;
 use super::js_task::JsTasksMap;
-use super::project::Project;
-use super::FileCache;
-use std::time::Instant;
 
 pub struct Runner {
-    pub cwd: String,
     pub async_tasks: JsTasksMap,
     pub project: SharedProject,
     pub cache: SharedCache,
-    pub on_finish: OnFinishTSFN,
-    pub on_resolve: ThreadsafeFunction<Vec<String>>,
+    pub graph: SharedGraph,
+    pub resolvers: ResolverRegistry,
+    pub options: RunOptions,
 }
 
-pub type SharedCache = Arc<RwLock<FileCache>>;
-pub type SharedProject = Arc<RwLock<Project>>;
-pub type OnFinishTSFN = ThreadsafeFunction<Vec<bool>>;
 pub type OnResolveTSFN = ThreadsafeFunction<Vec<String>>;
 
 impl Runner {
     pub fn new(
         cwd: String,
         cache: FileCache,
-        on_finish: OnFinishTSFN,
-        on_resolve: OnResolveTSFN,
+        on_resolve: Option<OnResolveTSFN>,
+        options: RunOptions,
     ) -> Self {
         let project = Project::create_or_cached(&cache, &cwd);
+        let async_tasks: JsTasksMap = Arc::new(RwLock::new(HashMap::new()));
+        let mut resolvers = ResolverRegistry::builtin();
+        if let Some(on_resolve) = on_resolve {
+            resolvers.register(Arc::new(JsResolver::new(
+                on_resolve,
+                Arc::clone(&async_tasks),
+            )));
+        }
+
         Self {
-            cwd,
-            on_finish,
-            on_resolve,
+            resolvers,
+            options,
             cache: Arc::new(RwLock::new(cache)),
             project: Arc::new(RwLock::new(project)),
-            async_tasks: Arc::new(RwLock::new(HashMap::new())),
+            graph: Arc::new(RwLock::new(None)),
+            async_tasks,
         }
     }
 }
 
-pub fn run(
-    shared_project: &SharedProject,
-    shared_async_tasks: &JsTasksMap,
-    shared_on_finish: &OnFinishTSFN,
-    shared_on_resolve: &OnResolveTSFN,
-    shared_cache: &SharedCache,
-) {
-    let shared_on_resolve_clone = shared_on_resolve.try_clone().unwrap();
-    let shared_on_finish_clone = shared_on_finish.try_clone().unwrap();
-    let shared_project_clone = Arc::clone(shared_project);
-    let shared_async_tasks_clone = Arc::clone(shared_async_tasks);
-    let shared_cache_clone = Arc::clone(shared_cache);
-
-    thread::spawn(move || {
-        let cache = shared_cache_clone
-            .read()
-            .expect("[runner:run] Couldn't lock read access to a cache");
-        let project = shared_project_clone
-            .read()
-            .expect("[runner:run] Couldn't lock read access to a project");
-
-        print!("Invalidating deps... ");
-        let now = Instant::now();
-        let (workspaces, updated) =
-            project.invalidate(shared_on_resolve_clone, &shared_async_tasks_clone);
-        drop(project);
-        println!("[{} ms]", now.elapsed().as_millis());
-
-        println!("Updated workspaces: {:?}", updated);
-
-        let mut project = shared_project_clone
-            .write()
-            .expect("[runner:run] Couldn't lock read access to a project");
-
-        project.workspaces = HashMap::new();
-        for ws in workspaces {
-            project.workspaces.insert(ws.name.to_owned(), ws);
-        }
-
-        println!("Building deps graph");
-        let dep_graph = DepGraph::new(project.workspaces.values().cloned().collect());
-
-        println!("Validating deps graph");
-        if dep_graph.validate() {
-            println!("Calculating affected dependencies");
-            println!(
-                "Affected dependencies: {:#?}",
-                dep_graph.get_affected(updated)
-            );
-
-            // CACHING
-            let serialized = serde_json::to_string(&project as &Project).unwrap();
-            cache.write("project.json", &serialized).unwrap();
-        }
-
-        shared_on_finish_clone.call(Ok(vec![]), ThreadsafeFunctionCallMode::NonBlocking);
-    });
-}
-
 pub fn on_complete_js_task(id: String, data: String, async_tasks: &JsTasksMap) {
     let map = async_tasks.read().expect("[orch]: RwLock");
     if map.contains_key(&id) {
         let mut state = map.get(&id).unwrap().lock().unwrap();
+        // Plugins are called when inputs are resolved, they can answer
+        // before the task is first polled.
+        state.completed = true;
+        state.data = Some(data);
         if let Some(waker) = state.waker.take() {
-            state.completed = true;
-            state.data = Some(data);
             waker.wake();
         }
     }